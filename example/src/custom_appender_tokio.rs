@@ -1,15 +1,12 @@
-use fast_log::appender::{FastLogRecord, LogAppender};
+use fast_log::appender::{AsyncLogAppender, FastLogRecord};
 use fast_log::config::Config;
 use fastdate::DateTime;
 use log::Level;
-use tokio::runtime::Runtime;
 
-struct CustomLog {
-    rt: Runtime,
-}
+struct CustomLog {}
 
-impl LogAppender for CustomLog {
-    fn do_logs(&self, records: &[FastLogRecord]) {
+impl AsyncLogAppender for CustomLog {
+    async fn do_logs(&self, records: &[FastLogRecord]) {
         let mut datas = String::new();
         for record in records {
             let now = DateTime::from(record.now);
@@ -30,25 +27,17 @@ impl LogAppender for CustomLog {
             }
             datas.push_str(&data);
         }
-        self.rt.block_on(async move {
-            //send to web,file,any way
-            print!("{}", datas);
-        });
+        //send to web,file,any way
+        print!("{}", datas);
     }
 }
 
 #[tokio::main]
 async fn main() {
-    fast_log::init(
-        Config::new().custom(CustomLog {
-            rt: tokio::runtime::Builder::new_multi_thread()
-                .enable_all()
-                .build()
-                .unwrap(),
-        }),
-    )
-    .unwrap();
+    //runs as a task of fast_log's runtime
+    fast_log::init(Config::new().custom_async(CustomLog {})).unwrap();
     log::info!("Commencing yak shaving");
     log::error!("Commencing error");
+    //flush blocks until the task has written the records
     log::logger().flush();
 }
@@ -29,10 +29,10 @@ impl Packer for DateLogPacker {
             }
         }
         //do nothing,and not remove file
-        let now = fastdate::DateTime::now()
-            .set_hour(0)
-            .set_min(0)
-            .set_sec(0)
+        let now = fastdate::DateTime::now();
+        let now = now
+            .clone()
+            .add_sub_sec(-(now.hour() as i64 * 3600 + now.minute() as i64 * 60 + now.sec() as i64))
             .set_nano(0);
         let name = self.new_log_name(log_file_path, now);
         let mut f = OpenOptions::new()
@@ -0,0 +1,16 @@
+use fast_log::config::Config;
+use fast_log::tracing::FastLogLayer;
+use tracing_subscriber::layer::SubscriberExt;
+
+fn main() {
+    //log records and tracing events share the same appenders
+    fast_log::init(Config::new().console()).unwrap();
+    let subscriber = tracing_subscriber::registry().with(FastLogLayer::new());
+    tracing::subscriber::set_global_default(subscriber).unwrap();
+    let span = tracing::info_span!("request", request_id = 42);
+    let _enter = span.enter();
+    //2024-01-01 10:00:00.000000 [INFO] Commencing yak shaving span=request request_id=42 yaks=3
+    tracing::info!(yaks = 3, "Commencing yak shaving");
+    log::info!("from log");
+    log::logger().flush();
+}
@@ -1,4 +1,11 @@
+use crate::error::LogError;
+use crate::metrics::AppenderCounters;
+use crate::pool::BUFFER_POOL;
 use crate::WaitGroup;
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::SystemTime;
 
 /// LogAppender append logs
@@ -6,6 +13,60 @@ use std::time::SystemTime;
 pub trait LogAppender: Send {
     /// Batch write log, or do nothing
     fn do_logs(&self, records: &[FastLogRecord]);
+
+    /// Batch write log and return the first error(for example disk full),
+    /// the error is passed to Config::error_handler. default calls do_logs and returns Ok.
+    /// records before the error may have been written, see ErrorAction::Retry
+    fn try_do_logs(&self, records: &[FastLogRecord]) -> Result<(), LogError> {
+        self.do_logs(records);
+        Ok(())
+    }
+
+    /// Batch write log shared with other appenders,this is what the pipeline calls.
+    /// default calls try_do_logs, override to keep the batch without copying it
+    fn do_logs_shared(&self, records: &Arc<Vec<FastLogRecord>>) -> Result<(), LogError> {
+        self.try_do_logs(records)
+    }
+
+    /// counters(rotations,packs...) shown in Logger::metrics,default None
+    fn counters(&self) -> Option<Arc<AppenderCounters>> {
+        None
+    }
+}
+
+/// an appender failed to write a batch,see Config::error_handler
+pub struct AppendError<'a> {
+    /// the index of the appender in Config.appends
+    pub appender: usize,
+    pub error: &'a LogError,
+    /// the batch that failed
+    pub records: &'a [FastLogRecord],
+    /// how many times the batch has been retried
+    pub retries: usize,
+}
+
+/// what the appender thread does with a failed batch
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// drop the batch
+    Ignore,
+    /// write the whole batch again with the same appender.
+    /// only for appenders that write a batch all-or-nothing: the file appenders may have written
+    /// part of the batch(and made packs) before the error, a retry writes those records twice
+    Retry,
+    /// write the whole batch with Config.fallback(default stderr).
+    /// like Retry, records the appender wrote before the error are written twice
+    Fallback,
+    /// drop the batch and stop writing to this appender
+    Disable,
+}
+
+/// LogAppender with an async do_logs, run as a task of a tokio runtime(feature runtime_tokio),
+/// see Config::custom_async
+#[cfg(feature = "runtime_tokio")]
+pub trait AsyncLogAppender: Send + Sync + 'static {
+    /// Batch write log, or do nothing
+    fn do_logs(&self, records: &[FastLogRecord]) -> impl std::future::Future<Output = ()> + Send;
 }
 
 #[derive(Clone, Debug)]
@@ -34,19 +95,180 @@ impl PartialEq for Command {
 
 impl Eq for Command {}
 
+/// one log record. target,module_path and file borrow the 'static str of the log macros
+/// when available, args and formated are buffers of BUFFER_POOL
 #[derive(Clone, Debug)]
 pub struct FastLogRecord {
     pub command: Command,
     pub level: log::Level,
-    pub target: String,
+    pub target: Cow<'static, str>,
     pub args: String,
-    pub module_path: String,
-    pub file: String,
+    pub module_path: Cow<'static, str>,
+    pub file: Cow<'static, str>,
     pub line: Option<u32>,
     pub now: SystemTime,
+    /// the id of the logging thread, numbered by fast_log in the order threads first log
+    pub thread_id: u64,
+    /// the name of the logging thread
+    pub thread_name: Option<Arc<str>>,
+    /// the id of the logging process
+    pub pid: u32,
     pub formated: String,
+    /// structured key-values,for example log::info!(request_id = 1; "done")
+    pub fields: Vec<(String, FieldValue)>,
+    /// the fast_log::context of the logging thread(or task) when the record was logged
+    pub context: Option<Arc<Vec<(String, FieldValue)>>>,
+}
+
+impl FastLogRecord {
+    /// an command(flush,exit) without log data
+    pub fn command(command: Command) -> Self {
+        Self {
+            command,
+            level: log::Level::Info,
+            target: Cow::Borrowed(""),
+            args: String::new(),
+            module_path: Cow::Borrowed(""),
+            file: Cow::Borrowed(""),
+            line: None,
+            now: SystemTime::now(),
+            thread_id: 0,
+            thread_name: None,
+            pid: 0,
+            formated: String::new(),
+            fields: vec![],
+            context: None,
+        }
+    }
+
+    /// an record of fast_log itself(for example "N records dropped") on the current thread
+    pub fn notice(level: log::Level, target: Cow<'static, str>, args: String) -> Self {
+        let (thread_id, thread_name) = current_thread();
+        Self {
+            command: Command::CommandRecord,
+            level,
+            module_path: target.clone(),
+            target,
+            args,
+            file: Cow::Borrowed(""),
+            line: None,
+            now: SystemTime::now(),
+            thread_id,
+            thread_name,
+            pid: current_pid(),
+            formated: String::new(),
+            fields: vec![],
+            context: None,
+        }
+    }
+}
+
+impl Drop for FastLogRecord {
+    fn drop(&mut self) {
+        BUFFER_POOL.put(std::mem::take(&mut self.args));
+        BUFFER_POOL.put(std::mem::take(&mut self.formated));
+    }
+}
+
+/// the next thread id, ids start at 1 in the order threads first log
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD: (u64, Option<Arc<str>>) = {
+        let id = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+        (id, std::thread::current().name().map(Arc::from))
+    };
+}
+
+/// (id, name) of the calling thread, cached per thread
+pub fn current_thread() -> (u64, Option<Arc<str>>) {
+    THREAD.with(|x| x.clone())
 }
 
+/// the id of the current process, not cached so it is right in a forked child
+pub fn current_pid() -> u32 {
+    std::process::id()
+}
+
+/// typed value of a structured field
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Str(String),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bool(bool),
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Str(v) => v.fmt(f),
+            FieldValue::I64(v) => v.fmt(f),
+            FieldValue::U64(v) => v.fmt(f),
+            FieldValue::F64(v) => v.fmt(f),
+            FieldValue::Bool(v) => v.fmt(f),
+        }
+    }
+}
+
+impl From<&log::kv::Value<'_>> for FieldValue {
+    fn from(value: &log::kv::Value<'_>) -> Self {
+        if let Some(v) = value.to_bool() {
+            FieldValue::Bool(v)
+        } else if let Some(v) = value.to_i64() {
+            FieldValue::I64(v)
+        } else if let Some(v) = value.to_u64() {
+            FieldValue::U64(v)
+        } else if let Some(v) = value.to_f64() {
+            FieldValue::F64(v)
+        } else if let Some(v) = value.to_borrowed_str() {
+            FieldValue::Str(v.to_string())
+        } else {
+            FieldValue::Str(value.to_string())
+        }
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        FieldValue::Str(value)
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        FieldValue::Bool(value)
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        FieldValue::F64(value)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($variant:ident, $target:ty, $($t:ty),*) => {
+        $(
+            impl From<$t> for FieldValue {
+                fn from(value: $t) -> Self {
+                    FieldValue::$variant(value as $target)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(I64, i64, i8, i16, i32, i64, isize);
+impl_from_int!(U64, u64, u8, u16, u32, u64, usize);
+
 /// format record data
 pub trait RecordFormat: Send + Sync {
     fn do_format(&self, arg: &mut FastLogRecord);
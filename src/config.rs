@@ -1,15 +1,18 @@
-use crate::appender::{LogAppender, RecordFormat};
+use crate::appender::{
+    AppendError, Command, ErrorAction, FastLogRecord, LogAppender, RecordFormat,
+};
 use crate::consts::LogSize;
 use crate::filter::{Filter, NoFilter};
-use crate::plugin::console::ConsoleAppender;
+use crate::plugin::console::{ConsoleAppender, StderrAppender};
 use crate::plugin::file::FileAppender;
 use crate::plugin::file_loop::FileLoopAppender;
 use crate::plugin::file_split::{FileSplitAppender, Keep, Packer, RawFile, SplitFile};
+use crate::suppress::RateLimit;
 use crate::FastLogFormat;
-use dark_std::sync::SyncVec;
 use log::LevelFilter;
 use parking_lot::Mutex;
 use std::fmt::{Debug, Formatter};
+use std::time::Duration;
 
 /// the fast_log Config
 /// for example:
@@ -18,7 +21,7 @@ use std::fmt::{Debug, Formatter};
 // )
 pub struct Config {
     /// Each appender is responsible for printing its own business
-    pub appends: SyncVec<Mutex<Box<dyn LogAppender>>>,
+    pub appends: Vec<AppendConfig>,
     /// the log level filter
     pub level: LevelFilter,
     /// filter log
@@ -27,26 +30,109 @@ pub struct Config {
     pub format: Box<dyn RecordFormat>,
     /// the channel length,default None(Unbounded channel)
     pub chan_len: Option<usize>,
+    /// what to do when the bounded channel is full,default Block
+    pub overflow: OverflowPolicy,
+    /// the number of threads formatting records,default 1
+    pub format_workers: usize,
+    /// decides what to do when an appender fails to write a batch,default ErrorAction::Fallback
+    pub error_handler: Box<dyn Fn(&AppendError) -> ErrorAction + Send + Sync>,
+    /// writes the batches of failed appenders,default StderrAppender,see ErrorAction::Fallback
+    pub fallback: Option<Mutex<Box<dyn LogAppender>>>,
+    /// log panics with backtrace and flush before the previous panic hook runs,default false
+    pub panic_hook: bool,
+    /// collapse consecutive identical records into `"[fast_log] last message repeated N times"`,default false
+    pub dedup: bool,
+    /// token bucket limit per target or call site,default None
+    pub rate_limit: Option<RateLimit>,
+    /// flush the appenders when records have been waiting this long,default None
+    pub flush_interval: Option<Duration>,
+    /// flush the appenders after records of this level or more severe,default None
+    pub flush_level: Option<log::Level>,
+    /// on SIGTERM/SIGINT log the signal and shut the LOGGER down,default false(feature signal)
+    #[cfg(all(unix, feature = "signal"))]
+    pub exit_on_signal: bool,
+}
+
+/// what Logger::log does when the bounded channel(chan_len=Some) is full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// block the caller until there is room
+    #[default]
+    Block,
+    /// block the caller at most Duration, then drop the record
+    BlockTimeout(Duration),
+    /// drop the record being logged
+    DropNewest,
+    /// drop the oldest record in the channel to make room
+    DropOldest,
 }
 
 impl Debug for Config {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Config")
-            .field("appends", &self.appends.len())
+        let mut f = f.debug_struct("Config");
+        f.field("appends", &self.appends.len())
             .field("level", &self.level)
             .field("chan_len", &self.chan_len)
-            .finish()
+            .field("overflow", &self.overflow)
+            .field("format_workers", &self.format_workers)
+            .field("fallback", &self.fallback.is_some())
+            .field("panic_hook", &self.panic_hook)
+            .field("dedup", &self.dedup)
+            .field("rate_limit", &self.rate_limit)
+            .field("flush_interval", &self.flush_interval)
+            .field("flush_level", &self.flush_level);
+        #[cfg(all(unix, feature = "signal"))]
+        f.field("exit_on_signal", &self.exit_on_signal);
+        f.finish()
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            appends: SyncVec::new(),
+            appends: Vec::new(),
             level: LevelFilter::Trace,
             filter: Box::new(NoFilter {}),
             format: Box::new(FastLogFormat::new()),
             chan_len: None,
+            overflow: OverflowPolicy::default(),
+            format_workers: 1,
+            error_handler: Box::new(|_| ErrorAction::Fallback),
+            fallback: Some(Mutex::new(Box::new(StderrAppender {}))),
+            panic_hook: false,
+            dedup: false,
+            rate_limit: None,
+            flush_interval: None,
+            flush_level: None,
+            #[cfg(all(unix, feature = "signal"))]
+            exit_on_signal: false,
+        }
+    }
+}
+
+/// an appender with its own level and format
+pub struct AppendConfig {
+    pub appender: Mutex<Box<dyn LogAppender>>,
+    /// records more verbose than this level are not sent to the appender,default Trace
+    pub level: LevelFilter,
+    /// if set,records are formatted with this format instead of Config's format
+    pub format: Option<Box<dyn RecordFormat>>,
+}
+
+impl AppendConfig {
+    pub fn new<Appender: LogAppender + 'static>(appender: Appender) -> Self {
+        Self {
+            appender: Mutex::new(Box::new(appender)),
+            level: LevelFilter::Trace,
+            format: None,
+        }
+    }
+
+    /// is the record need send to this appender
+    pub fn accept(&self, record: &FastLogRecord) -> bool {
+        match record.command {
+            Command::CommandRecord => record.level.to_level_filter() <= self.level,
+            _ => true,
         }
     }
 }
@@ -73,21 +159,17 @@ impl Config {
     }
     /// add a ConsoleAppender
     pub fn console(self) -> Self {
-        self.appends.push(Mutex::new(Box::new(ConsoleAppender {})));
-        self
+        self.custom(ConsoleAppender {})
     }
     /// add a FileAppender
     pub fn file(self, file: &str) -> Self {
-        self.appends
-            .push(Mutex::new(Box::new(FileAppender::new(file).unwrap())));
-        self
+        self.custom(FileAppender::new(file).unwrap())
     }
     /// add a FileLoopAppender
     pub fn file_loop(self, file: &str, max_temp_size: LogSize) -> Self {
-        self.appends.push(Mutex::new(Box::new(
+        self.custom(
             FileLoopAppender::<RawFile>::new(file, max_temp_size).expect("make file_loop fail"),
-        )));
-        self
+        )
     }
     /// add a FileSplitAppender
     pub fn file_split<P: Packer + Sync + 'static, R: Keep + 'static>(
@@ -97,11 +179,10 @@ impl Config {
         rolling_type: R,
         packer: P,
     ) -> Self {
-        self.appends.push(Mutex::new(Box::new(
+        self.custom(
             FileSplitAppender::<RawFile>::new(file_path, temp_size, rolling_type, Box::new(packer))
                 .unwrap(),
-        )));
-        self
+        )
     }
 
     /// add a SplitAppender
@@ -125,14 +206,52 @@ impl Config {
         keeper: R,
         packer: P,
     ) -> Self {
-        self.appends.push(Mutex::new(Box::new(
+        self.custom(
             FileSplitAppender::<F>::new(file_path, temp_size, keeper, Box::new(packer)).unwrap(),
-        )));
-        self
+        )
     }
     /// add a custom LogAppender
-    pub fn custom<Appender: LogAppender + 'static>(self, arg: Appender) -> Self {
-        self.appends.push(Mutex::new(Box::new(arg)));
+    pub fn custom<Appender: LogAppender + 'static>(mut self, arg: Appender) -> Self {
+        self.appends.push(AppendConfig::new(arg));
+        self
+    }
+
+    /// add an AsyncLogAppender, run as a task of fast_log's tokio runtime(feature runtime_tokio).
+    /// use AsyncAppender::with_handle with `custom` to run it on another runtime
+    /// for example:
+    // #[tokio::main]
+    // async fn main() {
+    //     fast_log::init(Config::new().custom_async(HttpAppender {})).unwrap();
+    // }
+    #[cfg(feature = "runtime_tokio")]
+    pub fn custom_async<Appender: crate::appender::AsyncLogAppender>(self, arg: Appender) -> Self {
+        self.custom(crate::plugin::async_appender::AsyncAppender::new(arg))
+    }
+
+    /// set the level of the last added appender.
+    /// the Config level is still checked first, so it must be at least as verbose.
+    /// for example:
+    // Config::new()
+    //     .level(LevelFilter::Debug)
+    //     .console()
+    //     .appender_level(LevelFilter::Info)
+    pub fn appender_level(mut self, level: LevelFilter) -> Self {
+        if let Some(last) = self.appends.last_mut() {
+            last.level = level;
+        }
+        self
+    }
+
+    /// set the format of the last added appender
+    /// for example:
+    // Config::new()
+    //     .console()
+    //     .file("target/logs/json.log")
+    //     .appender_format(FastLogFormatJson::new())
+    pub fn appender_format<F: RecordFormat + 'static>(mut self, format: F) -> Self {
+        if let Some(last) = self.appends.last_mut() {
+            last.format = Some(Box::new(format));
+        }
         self
     }
 
@@ -141,4 +260,95 @@ impl Config {
         self.chan_len = len;
         self
     }
+
+    /// set OverflowPolicy of the bounded channel.
+    /// dropped records are counted and reported as an "N records dropped" record
+    /// for example:
+    // Config::new()
+    //     .chan_len(Some(100000))
+    //     .overflow(OverflowPolicy::DropNewest)
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// set the number of threads formatting records(default 1),
+    /// appenders still receive records in the order they were logged.
+    /// useful for expensive formats, for example FastLogFormatJson at high volume
+    pub fn format_workers(mut self, workers: usize) -> Self {
+        self.format_workers = workers;
+        self
+    }
+
+    /// set the handler called when an appender fails to write a batch(for example disk full).
+    /// the handler is called again for every failed retry
+    /// for example:
+    // Config::new()
+    //     .file("target/logs/temp.log")
+    //     .fallback(StderrAppender {})
+    //     .error_handler(|e| {
+    //         eprintln!("appender {} failed: {}", e.appender, e.error);
+    //         ErrorAction::Fallback
+    //     })
+    pub fn error_handler<H>(mut self, handler: H) -> Self
+    where
+        H: Fn(&AppendError) -> ErrorAction + Send + Sync + 'static,
+    {
+        self.error_handler = Box::new(handler);
+        self
+    }
+
+    /// set the appender that writes the batches of failed appenders(default StderrAppender),
+    /// see ErrorAction::Fallback
+    pub fn fallback<Appender: LogAppender + 'static>(mut self, appender: Appender) -> Self {
+        self.fallback = Some(Mutex::new(Box::new(appender)));
+        self
+    }
+
+    /// install a panic hook(global LOGGER only): the panic payload,thread,location and backtrace
+    /// are logged at Error level and flushed, then the previous hook runs
+    pub fn panic_hook(mut self, enable: bool) -> Self {
+        self.panic_hook = enable;
+        self
+    }
+
+    /// handle SIGTERM/SIGINT(global LOGGER only,feature signal): log the signal at Warn level,
+    /// flush and shut the LOGGER down(at most 5s), then terminate the process.
+    /// if the signal already had a handler before, that handler is chained instead of terminating
+    #[cfg(all(unix, feature = "signal"))]
+    pub fn exit_on_signal(mut self, enable: bool) -> Self {
+        self.exit_on_signal = enable;
+        self
+    }
+
+    /// collapse consecutive identical records(level,target,message) into
+    /// one record and a `"[fast_log] last message repeated N times"` record
+    pub fn dedup(mut self, enable: bool) -> Self {
+        self.dedup = enable;
+        self
+    }
+
+    /// limit records per target or call site,
+    /// suppressed records are reported when the limit lets records through again
+    /// for example:
+    // Config::new()
+    //     .file_split(...)
+    //     .rate_limit(RateLimit::per_call_site(100, Duration::from_secs(1)))
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// flush the appenders(for example msync of MmapFile) at most `interval` after a record is written,
+    /// this bounds the logs lost by a crash without calling log::logger().flush()
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = Some(interval);
+        self
+    }
+
+    /// flush the appenders after records of `level` or more severe,for example Level::Error
+    pub fn flush_level(mut self, level: log::Level) -> Self {
+        self.flush_level = Some(level);
+        self
+    }
 }
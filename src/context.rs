@@ -0,0 +1,95 @@
+use crate::appender::FieldValue;
+use std::cell::RefCell;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// the fields of a context,shared by every record logged in it
+pub type ContextFields = Arc<Vec<(String, FieldValue)>>;
+
+thread_local! {
+    static CONTEXT: RefCell<Option<ContextFields>> = const { RefCell::new(None) };
+}
+
+/// add a field to the context of the current thread until the guard is dropped.
+/// records logged meanwhile carry the field, for example:
+// let _guard = fast_log::context::push("request_id", 42);
+// log::info!("done"); //... [INFO] done request_id=42
+pub fn push<K: Into<String>, V: Into<FieldValue>>(key: K, value: V) -> ContextGuard {
+    let current = current();
+    let mut fields = match &current {
+        Some(fields) => fields.as_ref().clone(),
+        None => Vec::with_capacity(1),
+    };
+    fields.push((key.into(), value.into()));
+    CONTEXT.with(|x| *x.borrow_mut() = Some(Arc::new(fields)));
+    ContextGuard {
+        previous: current,
+        _not_send: PhantomData,
+    }
+}
+
+/// the context of the current thread(or scoped future)
+pub fn current() -> Option<ContextFields> {
+    CONTEXT.with(|x| x.borrow().clone())
+}
+
+/// restores the context before `push` when dropped
+#[must_use = "the field is removed when the guard is dropped"]
+pub struct ContextGuard {
+    previous: Option<ContextFields>,
+    //the guard restores the context of the thread it was made on
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CONTEXT.with(|x| *x.borrow_mut() = previous);
+    }
+}
+
+/// run the future with the current context plus a field, for async code
+/// where a thread-local guard would leak into other tasks. for example:
+// fast_log::context::scope("request_id", 42, async {
+//     log::info!("done"); //... [INFO] done request_id=42
+// })
+// .await;
+pub fn scope<K, V, F>(key: K, value: V, future: F) -> Scoped<F>
+where
+    K: Into<String>,
+    V: Into<FieldValue>,
+    F: Future,
+{
+    let _guard = push(key, value);
+    in_current(future)
+}
+
+/// run the future with the current context, for example a task spawned from a request handler
+pub fn in_current<F: Future>(future: F) -> Scoped<F> {
+    Scoped {
+        context: current(),
+        future: Box::pin(future),
+    }
+}
+
+/// a future that has its own context while it is polled, see `scope`
+pub struct Scoped<F> {
+    context: Option<ContextFields>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let previous =
+            CONTEXT.with(|x| std::mem::replace(&mut *x.borrow_mut(), this.context.clone()));
+        let result = this.future.as_mut().poll(cx);
+        CONTEXT.with(|x| *x.borrow_mut() = previous);
+        result
+    }
+}
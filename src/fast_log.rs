@@ -1,57 +1,604 @@
-use crate::appender::{Command, FastLogRecord};
-use crate::config::Config;
+use crate::appender::{
+    current_pid, current_thread, AppendError, Command, ErrorAction, FastLogRecord, FieldValue,
+    LogAppender,
+};
+use crate::config::{Config, OverflowPolicy};
+use crate::context;
 use crate::error::LogError;
-use crate::{chan, spawn, Receiver, SendError, Sender, WaitGroup};
+use crate::metrics::{AppenderMetrics, Metrics, MetricsSnapshot};
+use crate::pool::BUFFER_POOL;
+use crate::suppress::Suppressor;
+use crate::{chan, spawn, JoinHandle, Receiver, SendError, Sender, WaitGroup};
+use crossbeam_channel::{RecvTimeoutError, SendTimeoutError, TrySendError};
+use log::kv::{self, Key, Value, VisitSource};
 use log::{LevelFilter, Log, Metadata, Record};
-use once_cell::sync::{Lazy, OnceCell};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
 use std::ops::Deref;
-use std::sync::Arc;
-use std::time::SystemTime;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Once};
+use std::time::{Duration, Instant, SystemTime};
 
-pub static LOGGER: Lazy<Logger> = Lazy::new(|| Logger {
-    cfg: OnceCell::new(),
-    send: OnceCell::new(),
-    recv: OnceCell::new(),
-});
+pub static LOGGER: Lazy<Logger> = Lazy::new(Logger::default);
 
+/// the running log pipeline: config, main channel and the worker threads
+pub(crate) struct Pipeline {
+    pub(crate) cfg: Arc<Config>,
+    pub(crate) send: Sender<FastLogRecord>,
+    pub(crate) recv: Receiver<FastLogRecord>,
+    handles: Mutex<Vec<JoinHandle<()>>>,
+    /// the channels of appender threads, for queue_len. the receivers are only held by
+    /// the appender threads, so the channel of a panicked appender disconnects
+    appender_send: Vec<Sender<Arc<Vec<FastLogRecord>>>>,
+    metrics: Arc<Metrics>,
+    /// dropped records not yet reported
+    dropped: AtomicU64,
+    /// the appender threads wait until it is dropped, see start
+    gate: Mutex<Option<Sender<()>>>,
+    /// appender errors for the ShutdownSummary
+    errors: Arc<Mutex<Vec<LogError>>>,
+}
+
+/// the appender errors kept for the ShutdownSummary, the rest are only counted(see AppenderSnapshot.errors)
+const MAX_ERRORS: usize = 100;
+
+impl Pipeline {
+    /// spawn the format thread and one thread per appender, the appenders write nothing until start.
+    /// records are queued(and block the callers once the bounded channels are full)
+    pub(crate) fn spawn_paused(config: Config) -> Result<Pipeline, LogError> {
+        if config.appends.is_empty() {
+            return Err(LogError::from("[fast_log] appends can not be empty!"));
+        }
+        let cfg = Arc::new(config);
+        let (send, recv) = chan::<FastLogRecord>(cfg.chan_len);
+        let mut handles = vec![];
+        let metrics = Arc::new(Metrics::new(
+            cfg.appends
+                .iter()
+                .map(|x| AppenderMetrics::new(x.appender.lock().counters()))
+                .collect(),
+        ));
+        let mut sender_vec: Vec<Sender<Arc<Vec<FastLogRecord>>>> = vec![];
+        let mut appender_send = vec![];
+        let (gate_send, gate) = chan::<()>(Some(1));
+        let errors = Arc::new(Mutex::new(vec![]));
+        for index in 0..cfg.appends.len() {
+            let (s, receiver) = chan::<Arc<Vec<FastLogRecord>>>(cfg.chan_len);
+            appender_send.push(s.clone());
+            sender_vec.push(s);
+            let cfg = cfg.clone();
+            let metrics = metrics.clone();
+            let gate = gate.clone();
+            let errors = errors.clone();
+            handles.push(spawn(move || {
+                //disconnected by start
+                let _ = gate.recv();
+                let mut exit = false;
+                let mut disabled = false;
+                while let Some(remain) = recv_all(&receiver) {
+                    let append = cfg.appends[index].appender.lock();
+                    for msg in remain {
+                        let mut records = 0;
+                        let mut bytes = 0;
+                        for x in msg.iter() {
+                            match x.command {
+                                Command::CommandRecord => {
+                                    records += 1;
+                                    bytes += x.formated.len() as u64;
+                                }
+                                Command::CommandExit => {
+                                    exit = true;
+                                }
+                                Command::CommandFlush(_) => {}
+                            }
+                        }
+                        if disabled {
+                            send_commands(&**append, &msg);
+                            continue;
+                        }
+                        let start = Instant::now();
+                        let mut retries = 0;
+                        let action = loop {
+                            //a panic is an error too, the thread keeps receiving,
+                            //so the flush and exit commands after it are not stuck in the channel
+                            let e = match catch_unwind(AssertUnwindSafe(|| {
+                                append.do_logs_shared(&msg)
+                            })) {
+                                Ok(Ok(())) => break None,
+                                Ok(Err(e)) => e,
+                                Err(_) => LogError::from("appender panicked"),
+                            };
+                            metrics.appenders[index]
+                                .errors
+                                .fetch_add(1, Ordering::Relaxed);
+                            push_error(&errors, format!("appender {} write fail", index), &e);
+                            let action = (cfg.error_handler)(&AppendError {
+                                appender: index,
+                                error: &e,
+                                records: msg.as_ref(),
+                                retries,
+                            });
+                            if action != ErrorAction::Retry {
+                                break Some(action);
+                            }
+                            retries += 1;
+                        };
+                        match action {
+                            None => {
+                                metrics.appenders[index].add_batch(records, bytes, start.elapsed());
+                            }
+                            Some(ErrorAction::Fallback) => {
+                                if let Some(fallback) = &cfg.fallback {
+                                    if let Err(e) = fallback.lock().try_do_logs(msg.as_ref()) {
+                                        metrics.appenders[index]
+                                            .errors
+                                            .fetch_add(1, Ordering::Relaxed);
+                                        let action = format!("appender {} fallback fail", index);
+                                        push_error(&errors, action, &e);
+                                    }
+                                }
+                            }
+                            Some(ErrorAction::Disable) => {
+                                disabled = true;
+                                send_commands(&**append, &msg);
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                    if exit {
+                        break;
+                    }
+                }
+            }));
+        }
+        let workers = cfg.format_workers.max(1);
+        if workers == 1 {
+            let recv = recv.clone();
+            let cfg = cfg.clone();
+            handles.push(spawn(move || {
+                let mut suppressor = Suppressor::new(&cfg);
+                let mut auto_flush = AutoFlush::new(&cfg);
+                while let Some(mut remain) =
+                    recv_all_timeout(&recv, format_timeout(&suppressor, &auto_flush))
+                {
+                    let exit = remain.iter().any(|x| x.command.eq(&Command::CommandExit));
+                    if let Some(suppressor) = &mut suppressor {
+                        remain = suppressor.process(remain);
+                        suppressor.idle(&mut remain);
+                    }
+                    auto_flush.process(&mut remain);
+                    if remain.is_empty() {
+                        continue;
+                    }
+                    for (x, data) in sender_vec.iter().zip(format_batch(&cfg, remain)) {
+                        let _ = x.send(data);
+                    }
+                    if exit {
+                        break;
+                    }
+                }
+            }));
+        } else {
+            //dispatch -> format workers -> collect in order -> appenders.
+            //bounded, so a slow appender still blocks(or drops at) the main channel
+            let (task_send, task_recv) = chan::<(u64, Vec<FastLogRecord>)>(Some(workers * 2));
+            let (done_send, done_recv) =
+                chan::<(u64, Vec<Arc<Vec<FastLogRecord>>>)>(Some(workers * 2));
+            for _ in 0..workers {
+                let task_recv = task_recv.clone();
+                let done_send = done_send.clone();
+                let cfg = cfg.clone();
+                handles.push(spawn(move || {
+                    while let Ok((seq, remain)) = task_recv.recv() {
+                        let _ = done_send.send((seq, format_batch(&cfg, remain)));
+                    }
+                }));
+            }
+            drop(done_send);
+            let recv = recv.clone();
+            let mut suppressor = Suppressor::new(&cfg);
+            let mut auto_flush = AutoFlush::new(&cfg);
+            handles.push(spawn(move || {
+                let mut seq = 0;
+                while let Some(mut remain) =
+                    recv_all_timeout(&recv, format_timeout(&suppressor, &auto_flush))
+                {
+                    let exit = remain.iter().any(|x| x.command.eq(&Command::CommandExit));
+                    if let Some(suppressor) = &mut suppressor {
+                        remain = suppressor.process(remain);
+                        suppressor.idle(&mut remain);
+                    }
+                    auto_flush.process(&mut remain);
+                    if remain.is_empty() {
+                        continue;
+                    }
+                    //split into one part per worker
+                    let size = remain.len().div_ceil(workers);
+                    while !remain.is_empty() {
+                        let rest = remain.split_off(size.min(remain.len()));
+                        let _ = task_send.send((seq, remain));
+                        seq += 1;
+                        remain = rest;
+                    }
+                    if exit {
+                        break;
+                    }
+                }
+            }));
+            handles.push(spawn(move || {
+                let mut next = 0;
+                let mut pending = HashMap::new();
+                while let Ok((seq, batches)) = done_recv.recv() {
+                    pending.insert(seq, batches);
+                    while let Some(batches) = pending.remove(&next) {
+                        next += 1;
+                        let exit = batches[0]
+                            .iter()
+                            .any(|x| x.command.eq(&Command::CommandExit));
+                        for (x, data) in sender_vec.iter().zip(batches) {
+                            let _ = x.send(data);
+                        }
+                        if exit {
+                            return;
+                        }
+                    }
+                }
+            }));
+        }
+        Ok(Self {
+            cfg,
+            send,
+            recv,
+            handles: Mutex::new(handles),
+            appender_send,
+            metrics,
+            dropped: AtomicU64::new(0),
+            gate: Mutex::new(Some(gate_send)),
+            errors,
+        })
+    }
+
+    /// let the appender threads write
+    pub(crate) fn start(&self) {
+        self.gate.lock().take();
+    }
+
+    /// send an record with the OverflowPolicy of Config.
+    /// commands(flush,exit) must use `send` directly, they are never dropped
+    pub(crate) fn push(&self, record: FastLogRecord) -> Result<(), SendError<FastLogRecord>> {
+        self.report_dropped();
+        let sent = match self.cfg.overflow {
+            OverflowPolicy::Block => {
+                self.send.send(record)?;
+                true
+            }
+            OverflowPolicy::BlockTimeout(timeout) => {
+                match self.send.send_timeout(record, timeout) {
+                    Ok(()) => true,
+                    Err(SendTimeoutError::Timeout(_)) => false,
+                    Err(SendTimeoutError::Disconnected(r)) => {
+                        return Err(crossbeam_channel::SendError(r));
+                    }
+                }
+            }
+            OverflowPolicy::DropNewest => match self.send.try_send(record) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => false,
+                Err(TrySendError::Disconnected(r)) => {
+                    return Err(crossbeam_channel::SendError(r));
+                }
+            },
+            OverflowPolicy::DropOldest => {
+                let mut record = record;
+                loop {
+                    match self.send.try_send(record) {
+                        Ok(()) => break,
+                        Err(TrySendError::Full(r)) => {
+                            record = r;
+                            if let Ok(old) = self.recv.try_recv() {
+                                if old.command == Command::CommandRecord {
+                                    self.add_dropped();
+                                } else {
+                                    //never drop commands,put it back
+                                    self.send.send(old)?;
+                                }
+                            }
+                        }
+                        Err(TrySendError::Disconnected(r)) => {
+                            return Err(crossbeam_channel::SendError(r));
+                        }
+                    }
+                }
+                true
+            }
+        };
+        if sent {
+            self.metrics.records.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.add_dropped();
+        }
+        Ok(())
+    }
+
+    /// total dropped records by OverflowPolicy
+    pub(crate) fn dropped(&self) -> u64 {
+        self.metrics.dropped.load(Ordering::Relaxed)
+    }
+
+    fn add_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// a snapshot of queue depth, throughput and appender counters
+    pub(crate) fn metrics(&self) -> MetricsSnapshot {
+        let uptime = self.metrics.start.elapsed();
+        let records = self.metrics.records.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            uptime,
+            queue_len: self.send.len(),
+            records,
+            records_per_sec: records as f64 / uptime.as_secs_f64().max(f64::EPSILON),
+            dropped: self.dropped(),
+            appenders: self
+                .metrics
+                .appenders
+                .iter()
+                .zip(self.appender_send.iter())
+                .map(|(m, s)| m.snapshot(s.len()))
+                .collect(),
+        }
+    }
+
+    /// if there is room again, send an "N records dropped" record
+    fn report_dropped(&self) {
+        if self.dropped.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+        let num = self.dropped.swap(0, Ordering::Relaxed);
+        if num == 0 {
+            return;
+        }
+        let record = FastLogRecord::notice(
+            log::Level::Warn,
+            Cow::Borrowed("fast_log"),
+            format!("[fast_log] {} records dropped", num),
+        );
+        if self.send.try_send(record).is_err() {
+            self.dropped.fetch_add(num, Ordering::Relaxed);
+        }
+    }
+
+    /// send exit command,then wait until every worker thread has written all records and stopped
+    pub(crate) fn shutdown(&self) -> ShutdownSummary {
+        let mut summary = ShutdownSummary::default();
+        self.start();
+        let exit = self.send.send(FastLogRecord::command(Command::CommandExit));
+        if exit.is_err() {
+            summary.errors.push(LogError::from("[fast_log] exit fail!"));
+        } else {
+            let handles = std::mem::take(&mut *self.handles.lock());
+            for h in handles {
+                if h.join().is_err() {
+                    summary
+                        .errors
+                        .push(LogError::from("[fast_log] worker thread panicked"));
+                }
+            }
+        }
+        summary.written = self
+            .metrics
+            .appenders
+            .iter()
+            .map(|x| x.records.load(Ordering::Relaxed))
+            .collect();
+        summary.dropped = self.dropped();
+        summary.errors.append(&mut self.errors.lock());
+        summary
+    }
+}
+
+/// what the pipeline has written when it was shut down
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownSummary {
+    /// records written by each appender,in the order of Config.appends
+    pub written: Vec<u64>,
+    /// records dropped by OverflowPolicy
+    pub dropped: u64,
+    /// failed exit or worker threads, then the appender write errors(the first 100)
+    pub errors: Vec<LogError>,
+}
+
+/// shutdown the global LOGGER when dropped,see fast_log::init_with_guard
+#[must_use = "the logger is shut down when the guard is dropped"]
+pub struct LogGuard {
+    logger: &'static Logger,
+}
+
+impl LogGuard {
+    pub fn logger(&self) -> &'static Logger {
+        self.logger
+    }
+
+    /// shutdown now and return the summary
+    pub fn shutdown(self) -> Option<ShutdownSummary> {
+        self.logger.shutdown()
+    }
+}
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        self.logger.shutdown();
+    }
+}
+
+/// the logger: owns a running Pipeline.
+/// use the global LOGGER by fast_log::init, or a standalone Logger by Logger::new
+/// for example:
+// let logger = Logger::new(Config::new().console()).unwrap();
+// //the log macros check log::max_level() first
+// log::set_max_level(LevelFilter::Trace);
+// log::info!(logger: logger, "Commencing yak shaving");
+// log::Log::flush(&logger);
 pub struct Logger {
-    pub cfg: OnceCell<Config>,
-    pub send: OnceCell<Sender<FastLogRecord>>,
-    pub recv: OnceCell<Receiver<FastLogRecord>>,
+    pub(crate) pipeline: RwLock<Option<Pipeline>>,
+    level: AtomicUsize,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self {
+            pipeline: RwLock::new(None),
+            level: AtomicUsize::new(LevelFilter::Trace as usize),
+        }
+    }
+}
+
+impl Drop for Logger {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
 }
 
 impl Logger {
+    /// make a standalone Logger,it is not the global log logger until `install`
+    pub fn new(config: Config) -> Result<Self, LogError> {
+        let logger = Self::default();
+        logger.reconfigure(config)?;
+        Ok(logger)
+    }
+
+    /// install this Logger as the global log logger, it is still usable(shutdown, metrics...) by the reference.
+    /// for example `Box::leak(Box::new(Logger::new(config)?)).install()?`
+    pub fn install(&'static self) -> Result<(), LogError> {
+        log::set_logger(self)?;
+        log::set_max_level(self.get_level());
+        Ok(())
+    }
+
+    /// is this Logger the global log logger
+    fn is_global(&self) -> bool {
+        std::ptr::eq(
+            log::logger() as *const dyn Log as *const (),
+            self as *const Logger as *const (),
+        )
+    }
+
     pub fn set_level(&self, level: LevelFilter) {
-        log::set_max_level(level);
+        self.level.store(level as usize, Ordering::Relaxed);
+        if self.is_global() {
+            log::set_max_level(level);
+        }
     }
 
     pub fn get_level(&self) -> LevelFilter {
-        log::max_level()
+        let level = self.level.load(Ordering::Relaxed);
+        LevelFilter::iter().nth(level).unwrap_or(LevelFilter::Trace)
+    }
+
+    /// the Config of the running pipeline
+    pub fn config(&self) -> Option<Arc<Config>> {
+        self.pipeline.read().as_ref().map(|p| p.cfg.clone())
+    }
+
+    /// send an command(or record) into the running pipeline, block if the channel is full
+    pub fn send(&self, record: FastLogRecord) -> Result<(), SendError<FastLogRecord>> {
+        match self.pipeline.read().as_ref() {
+            Some(pipeline) => pipeline.send.send(record),
+            None => Err(crossbeam_channel::SendError(record)),
+        }
+    }
+
+    /// metrics of the running pipeline, None if not running
+    pub fn metrics(&self) -> Option<MetricsSnapshot> {
+        self.pipeline.read().as_ref().map(|p| p.metrics())
+    }
+
+    /// total records dropped by the OverflowPolicy of the running pipeline
+    pub fn dropped(&self) -> u64 {
+        match self.pipeline.read().as_ref() {
+            Some(pipeline) => pipeline.dropped(),
+            None => 0,
+        }
+    }
+
+    /// start a new pipeline from config and replace the running one(if any).
+    /// records sent before the switch are written by the old appenders,
+    /// then the old threads are stopped and joined before the new appenders write anything,
+    /// so both may use the same file.
+    pub fn reconfigure(&self, config: Config) -> Result<(), LogError> {
+        let pipeline = Pipeline::spawn_paused(config)?;
+        let gate = pipeline.gate.lock().take();
+        let level = pipeline.cfg.level;
+        let old = {
+            let mut current = self.pipeline.write();
+            self.set_level(level);
+            current.replace(pipeline)
+        };
+        if let Some(old) = old {
+            old.shutdown();
+        }
+        drop(gate);
+        Ok(())
+    }
+
+    /// stop the running pipeline: wait until every appender has written all records
+    /// and every pending pack has finished. return None if not running
+    pub fn shutdown(&self) -> Option<ShutdownSummary> {
+        let pipeline = self.pipeline.write().take();
+        pipeline.map(|p| p.shutdown())
     }
 
     /// print no other info
     pub fn print(&self, log: String) -> Result<(), SendError<FastLogRecord>> {
-        let fast_log_record = FastLogRecord {
-            command: Command::CommandRecord,
-            level: log::Level::Info,
-            target: "".to_string(),
-            args: "".to_string(),
-            module_path: "".to_string(),
-            file: "".to_string(),
-            line: None,
-            now: SystemTime::now(),
-            formated: log,
-        };
-        if let Some(send) = LOGGER.send.get() {
-            send.send(fast_log_record)
-        } else {
-            // Ok(())
-            Err(crossbeam_channel::SendError(fast_log_record))
+        let mut fast_log_record =
+            FastLogRecord::notice(log::Level::Info, Cow::Borrowed(""), String::new());
+        fast_log_record.formated = log;
+        match self.pipeline.read().as_ref() {
+            Some(pipeline) => pipeline.push(fast_log_record),
+            None => Err(crossbeam_channel::SendError(fast_log_record)),
+        }
+    }
+
+    /// send an flush command,the WaitGroup is done when all appenders have flushed
+    pub fn send_flush(&self) -> Result<WaitGroup, LogError> {
+        let wg = WaitGroup::new();
+        let fast_log_record = FastLogRecord::command(Command::CommandFlush(wg.clone()));
+        if self.pipeline.read().is_none() {
+            return Err(LogError::from("not init"));
         }
+        let result = self.send(fast_log_record);
+        match result {
+            Ok(()) => {
+                return Ok(wg);
+            }
+            _ => {}
+        }
+        return Err(LogError::E("[fast_log] flush fail!".to_string()));
+    }
+
+    /// send an exit command,appenders stop after written all records before it
+    pub fn exit(&self) -> Result<(), LogError> {
+        let fast_log_record = FastLogRecord::command(Command::CommandExit);
+        if self.pipeline.read().is_none() {
+            return Err(LogError::from("not init"));
+        }
+        let result = self.send(fast_log_record);
+        match result {
+            Ok(()) => {
+                return Ok(());
+            }
+            _ => {}
+        }
+        return Err(LogError::E("[fast_log] exit fail!".to_string()));
     }
 
     pub fn wait(&self) {
-        self.flush();
+        Log::flush(self);
     }
 }
 
@@ -60,26 +607,58 @@ impl Log for Logger {
         metadata.level() <= self.get_level()
     }
     fn log(&self, record: &Record) {
-        if let Some(filter) = LOGGER.cfg.get() {
-            if let Some(send) = LOGGER.send.get() {
-                if !filter.filter.filter(record) {
-                    let _ = send.send(FastLogRecord {
-                        command: Command::CommandRecord,
-                        level: record.level(),
-                        target: record.metadata().target().to_string(),
-                        args: record.args().to_string(),
-                        module_path: record.module_path().unwrap_or_default().to_string(),
-                        file: record.file().unwrap_or_default().to_string(),
-                        line: record.line().clone(),
-                        now: SystemTime::now(),
-                        formated: String::new(),
-                    });
+        //the global logger leaves its level to log::max_level(see set_level),
+        //so log::set_max_level can raise it. standalone loggers share log::max_level, check their own level
+        if !self.is_global() && !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Some(pipeline) = self.pipeline.read().as_ref() {
+            if !pipeline.cfg.filter.filter(record) {
+                let mut fields = vec![];
+                let _ = record.key_values().visit(&mut FieldVisitor(&mut fields));
+                let mut args = BUFFER_POOL.get();
+                match record.args().as_str() {
+                    Some(s) => args.push_str(s),
+                    None => {
+                        let _ = args.write_fmt(*record.args());
+                    }
                 }
+                let module_path = match record.module_path_static() {
+                    Some(s) => Cow::Borrowed(s),
+                    None => Cow::Owned(record.module_path().unwrap_or_default().to_string()),
+                };
+                //the target is the module path by default
+                let target = if record.target() == module_path {
+                    module_path.clone()
+                } else {
+                    Cow::Owned(record.target().to_string())
+                };
+                let file = match record.file_static() {
+                    Some(s) => Cow::Borrowed(s),
+                    None => Cow::Owned(record.file().unwrap_or_default().to_string()),
+                };
+                let (thread_id, thread_name) = current_thread();
+                let _ = pipeline.push(FastLogRecord {
+                    command: Command::CommandRecord,
+                    level: record.level(),
+                    target,
+                    args,
+                    module_path,
+                    file,
+                    line: record.line(),
+                    now: SystemTime::now(),
+                    thread_id,
+                    thread_name,
+                    pid: current_pid(),
+                    formated: String::new(),
+                    fields,
+                    context: context::current(),
+                });
             }
         }
     }
     fn flush(&self) {
-        match flush() {
+        match self.send_flush() {
             Ok(v) => {
                 v.wait();
             }
@@ -88,171 +667,362 @@ impl Log for Logger {
     }
 }
 
-pub fn init(config: Config) -> Result<&'static Logger, LogError> {
-    if config.appends.is_empty() {
-        return Err(LogError::from("[fast_log] appends can not be empty!"));
-    }
-    let (s, r) = chan(config.chan_len);
-    LOGGER.send.set(s).map_err(|_| LogError::from("set fail"))?;
-    LOGGER.recv.set(r).map_err(|_| LogError::from("set fail"))?;
-    LOGGER.set_level(config.level);
-    LOGGER
-        .cfg
-        .set(config)
-        .map_err(|_| LogError::from("set fail="))?;
-    //main recv data
-    log::set_logger(LOGGER.deref())
-        .map(|()| log::set_max_level(LOGGER.cfg.get().unwrap().level))
-        .map_err(|e| LogError::from(e))?;
-
-    let mut receiver_vec = vec![];
-    let mut sender_vec: Vec<Sender<Arc<Vec<FastLogRecord>>>> = vec![];
-    let cfg = LOGGER.cfg.get().unwrap();
-    for a in cfg.appends.iter() {
-        let (s, r) = chan(cfg.chan_len);
-        sender_vec.push(s);
-        receiver_vec.push((r, a));
-    }
-    for (receiver, appender) in receiver_vec {
-        spawn(move || {
-            let mut exit = false;
-            loop {
-                let mut remain = vec![];
-                if receiver.len() == 0 {
-                    if let Ok(msg) = receiver.recv() {
-                        remain.push(msg);
-                    }
-                }
-                //recv all
-                loop {
-                    match receiver.try_recv() {
-                        Ok(v) => {
-                            remain.push(v);
-                        }
-                        Err(_) => {
-                            break;
-                        }
-                    }
-                }
-                let append = appender.lock();
-                for msg in remain {
-                    append.do_logs(msg.as_ref());
-                    for x in msg.iter() {
-                        match x.command {
-                            Command::CommandRecord => {}
-                            Command::CommandExit => {
-                                exit = true;
-                                continue;
-                            }
-                            Command::CommandFlush(_) => {
-                                continue;
-                            }
-                        }
+/// keep an appender error for the ShutdownSummary(the first MAX_ERRORS)
+fn push_error(errors: &Mutex<Vec<LogError>>, action: String, e: &LogError) {
+    let mut errors = errors.lock();
+    if errors.len() < MAX_ERRORS {
+        let LogError::E(msg) = e;
+        errors.push(LogError::from(format!("[fast_log] {}: {}", action, msg)));
+    }
+}
+
+/// give the flush and exit commands of records to a disabled appender,
+/// so it still flushes and stops its threads(for example the saver of FileSplitAppender)
+fn send_commands(append: &dyn LogAppender, records: &[FastLogRecord]) {
+    let commands: Vec<FastLogRecord> = records
+        .iter()
+        .filter(|x| x.command != Command::CommandRecord)
+        .cloned()
+        .collect();
+    if !commands.is_empty() {
+        let _ = catch_unwind(AssertUnwindSafe(|| append.try_do_logs(&commands)));
+    }
+}
+
+/// block until there is data, then receive all of it. return None if the channel is disconnected
+fn recv_all<T>(recv: &Receiver<T>) -> Option<Vec<T>> {
+    recv_all_timeout(recv, None)
+}
+
+/// like recv_all, but return an empty Vec if there is no data within timeout
+fn recv_all_timeout<T>(recv: &Receiver<T>, timeout: Option<Duration>) -> Option<Vec<T>> {
+    let mut remain = Vec::with_capacity(recv.len());
+    //recv
+    if recv.len() == 0 {
+        let item = match timeout {
+            None => recv.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(timeout) => recv.recv_timeout(timeout),
+        };
+        match item {
+            Ok(item) => remain.push(item),
+            Err(RecvTimeoutError::Timeout) => return Some(remain),
+            Err(RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+    //recv all
+    while let Ok(v) = recv.try_recv() {
+        remain.push(v);
+    }
+    Some(remain)
+}
+
+/// how long the format thread may wait for records: until the next auto flush or suppress summary
+fn format_timeout(suppressor: &Option<Suppressor>, auto_flush: &AutoFlush) -> Option<Duration> {
+    let summary = suppressor.as_ref().and_then(|x| x.timeout());
+    match (summary, auto_flush.timeout()) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// adds flush commands to the batches of the format thread,
+/// see Config::flush_interval and Config::flush_level
+struct AutoFlush {
+    interval: Option<Duration>,
+    level: Option<log::Level>,
+    last: Instant,
+    /// records sent since the last flush
+    dirty: bool,
+}
+
+impl AutoFlush {
+    fn new(cfg: &Config) -> Self {
+        Self {
+            interval: cfg.flush_interval,
+            level: cfg.flush_level,
+            last: Instant::now(),
+            dirty: false,
+        }
+    }
+
+    /// how long the format thread may wait for records, None is forever
+    fn timeout(&self) -> Option<Duration> {
+        match self.interval {
+            Some(interval) if self.dirty => Some(interval.saturating_sub(self.last.elapsed())),
+            _ => None,
+        }
+    }
+
+    fn process(&mut self, remain: &mut Vec<FastLogRecord>) {
+        let mut flush = false;
+        for x in remain.iter() {
+            match x.command {
+                Command::CommandRecord => {
+                    self.dirty = true;
+                    if self.level.map(|l| x.level <= l).unwrap_or(false) {
+                        flush = true;
                     }
                 }
-                if exit {
-                    break;
+                Command::CommandFlush(_) => {
+                    self.dirty = false;
+                    self.last = Instant::now();
                 }
+                //appenders flush on exit
+                Command::CommandExit => return,
             }
-        });
+        }
+        if let Some(interval) = self.interval {
+            if self.dirty && self.last.elapsed() >= interval {
+                flush = true;
+            }
+        }
+        if flush && self.dirty {
+            remain.push(FastLogRecord::command(Command::CommandFlush(
+                WaitGroup::new(),
+            )));
+            self.dirty = false;
+            self.last = Instant::now();
+        }
     }
-    let sender_vec = Arc::new(sender_vec);
-    for _ in 0..1 {
-        let senders = sender_vec.clone();
-        spawn(move || {
-            loop {
-                let recv = LOGGER.recv.get().unwrap();
-                let mut remain = Vec::with_capacity(recv.len());
-                //recv
-                if recv.len() == 0 {
-                    if let Ok(item) = recv.recv() {
-                        remain.push(item);
-                    }
-                }
-                //recv all
-                loop {
-                    match recv.try_recv() {
-                        Ok(v) => {
-                            remain.push(v);
-                        }
-                        Err(_) => {
-                            break;
-                        }
-                    }
-                }
-                let mut exit = false;
-                for x in &mut remain {
-                    if x.formated.is_empty() {
-                        LOGGER.cfg.get().unwrap().format.do_format(x);
-                    }
-                    if x.command.eq(&Command::CommandExit) {
-                        exit = true;
-                    }
-                }
-                let data = Arc::new(remain);
-                for x in senders.iter() {
-                    let _ = x.send(data.clone());
-                }
-                if exit {
-                    break;
+}
+
+/// format records, return the records of each appender(in the order of Config.appends)
+fn format_batch(cfg: &Config, mut remain: Vec<FastLogRecord>) -> Vec<Arc<Vec<FastLogRecord>>> {
+    //appenders with their own format get their own copy
+    let mut owned = HashMap::new();
+    for (index, append) in cfg.appends.iter().enumerate() {
+        if let Some(format) = &append.format {
+            let mut records = Vec::with_capacity(remain.len());
+            for x in remain.iter().filter(|x| append.accept(x)) {
+                let mut x = x.clone();
+                if x.formated.is_empty() {
+                    x.formated = BUFFER_POOL.get();
+                    format.do_format(&mut x);
                 }
+                records.push(x);
             }
-        });
+            owned.insert(index, Arc::new(records));
+        }
+    }
+    for x in &mut remain {
+        if x.formated.is_empty() {
+            x.formated = BUFFER_POOL.get();
+            cfg.format.do_format(x);
+        }
+    }
+    for (index, append) in cfg.appends.iter().enumerate() {
+        if append.format.is_none() && append.level < LevelFilter::Trace {
+            let records = remain.iter().filter(|x| append.accept(x)).cloned();
+            owned.insert(index, Arc::new(records.collect()));
+        }
+    }
+    let data = Arc::new(remain);
+    (0..cfg.appends.len())
+        .map(|index| owned.remove(&index).unwrap_or_else(|| data.clone()))
+        .collect()
+}
+
+/// collect log kv pairs into FastLogRecord fields
+struct FieldVisitor<'a>(&'a mut Vec<(String, FieldValue)>);
+
+impl<'kvs> VisitSource<'kvs> for FieldVisitor<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0
+            .push((key.as_str().to_string(), FieldValue::from(&value)));
+        Ok(())
+    }
+}
+
+pub fn init(config: Config) -> Result<&'static Logger, LogError> {
+    if LOGGER.pipeline.read().is_some() {
+        return Err(LogError::from(
+            "[fast_log] already init, use fast_log::reconfigure() to change Config",
+        ));
+    }
+    let panic_hook = config.panic_hook;
+    #[cfg(all(unix, feature = "signal"))]
+    let exit_on_signal = config.exit_on_signal;
+    LOGGER.reconfigure(config)?;
+    //main recv data
+    //LOGGER may be installed already(init after shutdown)
+    if let Err(e) = log::set_logger(LOGGER.deref()) {
+        if !LOGGER.is_global() {
+            LOGGER.shutdown();
+            return Err(LogError::from(e));
+        }
+    }
+    log::set_max_level(LOGGER.get_level());
+    if panic_hook {
+        install_panic_hook();
+    }
+    #[cfg(all(unix, feature = "signal"))]
+    if exit_on_signal {
+        install_signal_handler()?;
     }
     return Ok(LOGGER.deref());
 }
 
-pub fn exit() -> Result<(), LogError> {
-    let fast_log_record = FastLogRecord {
-        command: Command::CommandExit,
-        level: log::Level::Info,
-        target: String::new(),
-        args: String::new(),
-        module_path: String::new(),
-        file: String::new(),
-        line: None,
-        now: SystemTime::now(),
-        formated: String::new(),
+/// init the global LOGGER and return a guard, the LOGGER is shut down when the guard is dropped.
+/// for example:
+// fn main() {
+//     let _guard = fast_log::init_with_guard(Config::new().console()).unwrap();
+//     log::info!("written before main returns");
+// }
+pub fn init_with_guard(config: Config) -> Result<LogGuard, LogError> {
+    let logger = init(config)?;
+    Ok(LogGuard { logger })
+}
+
+/// replace the Config(appenders,format,filter,level...) of the global LOGGER at runtime.
+/// in-flight records are drained by the old appenders before they are dropped.
+pub fn reconfigure(config: Config) -> Result<(), LogError> {
+    if LOGGER.pipeline.read().is_none() {
+        return Err(LogError::from("not init"));
+    }
+    let panic_hook = config.panic_hook;
+    #[cfg(all(unix, feature = "signal"))]
+    let exit_on_signal = config.exit_on_signal;
+    LOGGER.reconfigure(config)?;
+    if panic_hook {
+        install_panic_hook();
+    }
+    #[cfg(all(unix, feature = "signal"))]
+    if exit_on_signal {
+        install_signal_handler()?;
+    }
+    Ok(())
+}
+
+/// chain the panic hook of Config::panic_hook before the current one, only once.
+/// the hook does nothing if the running Config has panic_hook disabled
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if LOGGER.config().map(|c| c.panic_hook).unwrap_or(false) {
+                log_panic(info);
+            }
+            previous(info);
+        }));
+    });
+}
+
+/// log the panic at Error level, then flush(at most 5s, the panic may be in an appender thread)
+fn log_panic(info: &std::panic::PanicHookInfo) {
+    let payload = match info.payload().downcast_ref::<&str>() {
+        Some(s) => *s,
+        None => match info.payload().downcast_ref::<String>() {
+            Some(s) => s.as_str(),
+            None => "Box<dyn Any>",
+        },
     };
-    let result = LOGGER
-        .send
-        .get()
-        .ok_or_else(|| LogError::from("not init"))?
-        .send(fast_log_record);
-    match result {
-        Ok(()) => {
-            return Ok(());
+    let thread = std::thread::current();
+    let name = thread.name().unwrap_or("<unnamed>");
+    let backtrace = std::backtrace::Backtrace::force_capture();
+    let at = match info.location() {
+        Some(location) => format!(" at {}", location),
+        None => String::new(),
+    };
+    LOGGER.log(
+        &Record::builder()
+            .level(log::Level::Error)
+            .target("panic")
+            .file(info.location().map(|x| x.file()))
+            .line(info.location().map(|x| x.line()))
+            .args(format_args!(
+                "thread '{}' panicked{}:\n{}\nstack backtrace:\n{}",
+                name, at, payload, backtrace
+            ))
+            .build(),
+    );
+    if let Ok(wg) = LOGGER.send_flush() {
+        let (done, wait) = chan::<()>(Some(1));
+        std::thread::spawn(move || {
+            wg.wait();
+            let _ = done.send(());
+        });
+        let _ = wait.recv_timeout(Duration::from_secs(5));
+    }
+}
+
+/// handle SIGTERM/SIGINT on a signal thread, only once(feature signal).
+/// the handler does nothing if the running Config has exit_on_signal disabled
+#[cfg(all(unix, feature = "signal"))]
+fn install_signal_handler() -> Result<(), LogError> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+    static INSTALL: Once = Once::new();
+    let mut result = Ok(());
+    INSTALL.call_once(|| {
+        //handlers installed before fast_log are chained by signal-hook
+        let chained = [SIGTERM, SIGINT].map(|signal| (signal, has_handler(signal)));
+        result = Signals::new([SIGTERM, SIGINT])
+            .and_then(|mut signals| {
+                std::thread::Builder::new()
+                    .name("fast_log_signal".to_string())
+                    .spawn(move || {
+                        for signal in signals.forever() {
+                            let chained = chained.iter().any(|(s, c)| *s == signal && *c);
+                            on_signal(signal, chained);
+                        }
+                    })
+            })
+            .map(|_| ())
+            .map_err(LogError::from);
+    });
+    result
+}
+
+/// true if the signal has a handler(not SIG_DFL or SIG_IGN)
+#[cfg(all(unix, feature = "signal"))]
+fn has_handler(signal: i32) -> bool {
+    unsafe {
+        let mut old: libc::sigaction = std::mem::zeroed();
+        if libc::sigaction(signal, std::ptr::null(), &mut old) != 0 {
+            return false;
         }
-        _ => {}
+        old.sa_sigaction != libc::SIG_DFL && old.sa_sigaction != libc::SIG_IGN
+    }
+}
+
+/// log the signal, shut the LOGGER down(at most 5s), then terminate like the default
+/// action would, unless a handler installed before fast_log has been chained
+#[cfg(all(unix, feature = "signal"))]
+fn on_signal(signal: i32, chained: bool) {
+    if LOGGER.config().map(|c| c.exit_on_signal).unwrap_or(false) {
+        let name = signal_hook::low_level::signal_name(signal).unwrap_or("signal");
+        LOGGER.log(
+            &Record::builder()
+                .level(log::Level::Warn)
+                .target("signal")
+                .args(format_args!("received {}, shutting down", name))
+                .build(),
+        );
+        let (done, wait) = chan::<()>(Some(1));
+        std::thread::spawn(move || {
+            LOGGER.shutdown();
+            let _ = done.send(());
+        });
+        let _ = wait.recv_timeout(Duration::from_secs(5));
     }
-    return Err(LogError::E("[fast_log] exit fail!".to_string()));
+    if !chained {
+        let _ = signal_hook::low_level::emulate_default_handler(signal);
+    }
+}
+
+pub fn exit() -> Result<(), LogError> {
+    LOGGER.exit()
+}
+
+/// stop the global LOGGER: block until every appender has written all records
+/// and every pending pack has finished
+pub fn shutdown() -> Result<ShutdownSummary, LogError> {
+    LOGGER.shutdown().ok_or_else(|| LogError::from("not init"))
 }
 
 pub fn flush() -> Result<WaitGroup, LogError> {
-    let wg = WaitGroup::new();
-    let fast_log_record = FastLogRecord {
-        command: Command::CommandFlush(wg.clone()),
-        level: log::Level::Info,
-        target: String::new(),
-        args: String::new(),
-        module_path: String::new(),
-        file: String::new(),
-        line: None,
-        now: SystemTime::now(),
-        formated: String::new(),
-    };
-    let result = LOGGER
-        .send
-        .get()
-        .ok_or_else(|| LogError::from("not init"))?
-        .send(fast_log_record);
-    match result {
-        Ok(()) => {
-            return Ok(wg);
-        }
-        _ => {}
-    }
-    return Err(LogError::E("[fast_log] flush fail!".to_string()));
+    LOGGER.send_flush()
 }
 
 pub fn print(log: String) -> Result<(), SendError<FastLogRecord>> {
@@ -1,3 +1,10 @@
+use log::kv::Key;
+use log::Level;
+use parking_lot::Mutex;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
 ///log filter
 pub trait Filter: Send + Sync {
     //return is filter
@@ -64,3 +71,135 @@ impl Filter for ModuleFilter {
         return false;
     }
 }
+
+/// how the records of one level are sampled by SamplingFilter
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sample {
+    /// keep every record
+    All,
+    /// keep the first of every N records of a target
+    OneIn(u64),
+    /// keep a percentage(0.0-100.0) of the records at random
+    Percent(f64),
+    /// keep every record of the sampled percentage(0.0-100.0) of a key,
+    /// for example all records of 1% of request ids.
+    /// the key is looked up in the record key-values, then in fast_log::context.
+    /// records without the key are kept
+    Key(String, f64),
+}
+
+/// sample records per level, Error records are never sampled
+/// for example:
+// Config::new().filter(
+//     SamplingFilter::new()
+//         .level(Level::Debug, Sample::OneIn(100))
+//         .level(Level::Trace, Sample::Key("request_id".to_string(), 1.0)),
+// )
+pub struct SamplingFilter {
+    /// indexed by log::Level as usize - 1
+    levels: [Sample; 5],
+    /// records seen per target of each level(indexed like levels), for Sample::OneIn
+    counters: Mutex<[HashMap<String, u64>; 5]>,
+}
+
+impl Default for SamplingFilter {
+    fn default() -> Self {
+        Self {
+            levels: [
+                Sample::All,
+                Sample::All,
+                Sample::All,
+                Sample::All,
+                Sample::All,
+            ],
+            counters: Mutex::new(Default::default()),
+        }
+    }
+}
+
+impl SamplingFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set the Sample of a level, ignored for Level::Error
+    pub fn level(mut self, level: Level, sample: Sample) -> Self {
+        if level != Level::Error {
+            self.levels[level as usize - 1] = sample;
+        }
+        self
+    }
+
+    fn keep(&self, record: &log::Record) -> bool {
+        match &self.levels[record.level() as usize - 1] {
+            Sample::All => true,
+            Sample::OneIn(n) => {
+                let mut counters = self.counters.lock();
+                let counters = &mut counters[record.level() as usize - 1];
+                let count = match counters.get_mut(record.target()) {
+                    Some(count) => count,
+                    None => counters.entry(record.target().to_string()).or_insert(0),
+                };
+                let keep = *count % (*n).max(1) == 0;
+                *count += 1;
+                keep
+            }
+            Sample::Percent(percent) => (random() % 10000) < (percent * 100.0) as u64,
+            Sample::Key(key, percent) => {
+                let hash = match record.key_values().get(Key::from_str(key)) {
+                    Some(value) => fnv1a(value.to_string().as_bytes()),
+                    None => {
+                        let context = crate::context::current();
+                        let value = context
+                            .as_ref()
+                            .and_then(|x| x.iter().find(|(k, _)| k == key).map(|(_, v)| v));
+                        match value {
+                            Some(value) => fnv1a(value.to_string().as_bytes()),
+                            None => return true,
+                        }
+                    }
+                };
+                (hash % 10000) < (percent * 100.0) as u64
+            }
+        }
+    }
+}
+
+/// 64-bit FNV-1a, the same key is sampled the same way by every process and fast_log release
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+impl Filter for SamplingFilter {
+    fn filter(&self, record: &log::Record) -> bool {
+        !self.keep(record)
+    }
+}
+
+/// xorshift random number of the current thread
+fn random() -> u64 {
+    thread_local! {
+        static STATE: Cell<u64> = Cell::new({
+            let nanos = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64;
+            //different seeds for threads started at the same time
+            let local = 0u8;
+            (nanos ^ (&local as *const u8 as u64)) | 1
+        });
+    }
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        x
+    })
+}
@@ -1,5 +1,8 @@
-use crate::appender::{Command, FastLogRecord, RecordFormat};
+use crate::appender::{Command, FastLogRecord, FieldValue, RecordFormat};
+use crate::error::LogError;
 use log::LevelFilter;
+use std::fmt::Write;
+use std::time::UNIX_EPOCH;
 
 pub enum TimeType {
     Local,
@@ -17,6 +20,10 @@ pub struct FastLogFormat {
     // show line level
     pub display_line_level: LevelFilter,
     pub time_type: TimeType,
+    // show [thread_name:thread_id]
+    pub display_thread: bool,
+    // show [pid]
+    pub display_pid: bool,
 }
 
 impl RecordFormat for FastLogFormat {
@@ -29,18 +36,32 @@ impl RecordFormat for FastLogFormat {
                         .display_stand(),
                     TimeType::Utc => fastdate::DateTime::from(arg.now).display_stand(),
                 };
+                //write into the (pooled) formated buffer
+                let buf = &mut arg.formated;
+                buf.clear();
+                let _ = write!(buf, "{:27} [{}]", &now, arg.level);
+                if self.display_pid {
+                    let _ = write!(buf, " [{}]", arg.pid);
+                }
+                if self.display_thread {
+                    match &arg.thread_name {
+                        Some(name) => {
+                            let _ = write!(buf, " [{}:{}]", name, arg.thread_id);
+                        }
+                        None => {
+                            let _ = write!(buf, " [{}]", arg.thread_id);
+                        }
+                    }
+                }
                 if arg.level.to_level_filter() <= self.display_line_level {
-                    arg.formated = format!(
-                        "{:27} [{}] [{}:{}] {}\n",
-                        &now,
-                        arg.level,
-                        arg.file,
-                        arg.line.unwrap_or_default(),
-                        arg.args,
-                    );
-                } else {
-                    arg.formated = format!("{:27} [{}] {}\n", &now, arg.level, arg.args);
+                    let _ = write!(buf, " [{}:{}]", arg.file, arg.line.unwrap_or_default());
                 }
+                let _ = write!(buf, " {}", arg.args);
+                if let Some(context) = &arg.context {
+                    write_fields_text(buf, context);
+                }
+                write_fields_text(buf, &arg.fields);
+                buf.push('\n');
             }
             Command::CommandExit => {}
             Command::CommandFlush(_) => {}
@@ -53,6 +74,8 @@ impl FastLogFormat {
         Self {
             display_line_level: LevelFilter::Warn,
             time_type: TimeType::default(),
+            display_thread: false,
+            display_pid: false,
         }
     }
 
@@ -67,16 +90,106 @@ impl FastLogFormat {
         self.time_type = time_type;
         self
     }
+
+    /// show `[thread_name:thread_id]` of the logging thread
+    pub fn set_display_thread(mut self, display: bool) -> Self {
+        self.display_thread = display;
+        self
+    }
+
+    /// show `[pid]` of the logging process
+    pub fn set_display_pid(mut self, display: bool) -> Self {
+        self.display_pid = display;
+        self
+    }
+}
+
+/// a member of the json object written by FastLogFormatJson
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonField {
+    /// the message
+    Args,
+    Date,
+    File,
+    Level,
+    Line,
+    Target,
+    Module,
+    Pid,
+    ThreadId,
+    /// omitted if the thread has no name
+    ThreadName,
+}
+
+impl JsonField {
+    /// the default key
+    pub fn key(&self) -> &'static str {
+        match self {
+            JsonField::Args => "args",
+            JsonField::Date => "date",
+            JsonField::File => "file",
+            JsonField::Level => "level",
+            JsonField::Line => "line",
+            JsonField::Target => "target",
+            JsonField::Module => "module_path",
+            JsonField::Pid => "pid",
+            JsonField::ThreadId => "thread_id",
+            JsonField::ThreadName => "thread_name",
+        }
+    }
+}
+
+/// the order of the members added by set_display_pid and set_display_thread
+fn display_order(field: JsonField) -> u8 {
+    match field {
+        JsonField::Pid => 1,
+        JsonField::ThreadId => 2,
+        JsonField::ThreadName => 3,
+        _ => 0,
+    }
+}
+
+/// how FastLogFormatJson writes the date
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsonTime {
+    /// "2022-08-19 09:53:47.798674"
+    #[default]
+    Stand,
+    /// "2022-08-19T09:53:47.798674Z", or with the local offset "+08:00"
+    Rfc3339,
+    /// milliseconds since the unix epoch
+    EpochMillis,
+    /// seconds since the unix epoch, with the fraction
+    EpochSecs,
 }
 
 pub struct FastLogFormatJson {
     pub time_type: TimeType,
+    pub json_time: JsonTime,
+    /// the members and their keys, in order
+    pub fields: Vec<(JsonField, String)>,
+    /// members added to every record, for example service,env,host
+    pub static_fields: Vec<(String, FieldValue)>,
 }
 
 impl Default for FastLogFormatJson {
     fn default() -> Self {
         Self {
             time_type: TimeType::default(),
+            json_time: JsonTime::default(),
+            fields: [
+                JsonField::Args,
+                JsonField::Date,
+                JsonField::File,
+                JsonField::Level,
+                JsonField::Line,
+                JsonField::Target,
+                JsonField::Module,
+            ]
+            .iter()
+            .map(|x| (*x, x.key().to_string()))
+            .collect(),
+            static_fields: vec![],
         }
     }
 }
@@ -85,25 +198,205 @@ impl RecordFormat for FastLogFormatJson {
     fn do_format(&self, arg: &mut FastLogRecord) {
         match &arg.command {
             Command::CommandRecord => {
+                //{"args":"Commencing yak shaving","date":"2022-08-19 09:53:47.798674","file":"example/src/split_log.rs","level":"INFO","line":21,...}
+                let mut buf = std::mem::take(&mut arg.formated);
+                buf.clear();
+                buf.push('{');
+                for (field, key) in &self.fields {
+                    if *field == JsonField::ThreadName && arg.thread_name.is_none() {
+                        continue;
+                    }
+                    if !buf.ends_with('{') {
+                        buf.push(',');
+                    }
+                    write_json_string(&mut buf, key);
+                    buf.push(':');
+                    self.write_field(&mut buf, *field, arg);
+                }
+                for (k, v) in &self.static_fields {
+                    write_json_member(&mut buf, k, v);
+                }
+                if let Some(context) = &arg.context {
+                    write_json_fields(&mut buf, context);
+                }
+                write_json_fields(&mut buf, &arg.fields);
+                buf.push_str("}\n");
+                arg.formated = buf;
+            }
+            Command::CommandExit => {}
+            Command::CommandFlush(_) => {}
+        }
+    }
+}
+
+impl FastLogFormatJson {
+    pub fn new() -> FastLogFormatJson {
+        Self::default()
+    }
+
+    /// set time_type
+    pub fn set_time_type(mut self, time_type: TimeType) -> Self {
+        self.time_type = time_type;
+        self
+    }
+
+    /// set how the date is written
+    pub fn set_json_time(mut self, json_time: JsonTime) -> Self {
+        self.json_time = json_time;
+        self
+    }
+
+    /// write only these members, in this order(with their default keys)
+    pub fn set_fields(mut self, fields: &[JsonField]) -> Self {
+        self.fields = fields.iter().map(|x| (*x, x.key().to_string())).collect();
+        self
+    }
+
+    /// write the member under another key, for example Args as "message"
+    pub fn rename(mut self, field: JsonField, key: &str) -> Self {
+        for (f, k) in self.fields.iter_mut() {
+            if *f == field {
+                *k = key.to_string();
+            }
+        }
+        self
+    }
+
+    /// add a member to every record
+    pub fn static_field<V: Into<FieldValue>>(mut self, key: &str, value: V) -> Self {
+        self.static_fields.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// add "thread_id" and "thread_name" of the logging thread
+    pub fn set_display_thread(self, display: bool) -> Self {
+        self.set_display(&[JsonField::ThreadId, JsonField::ThreadName], display)
+    }
+
+    /// add "pid" of the logging process
+    pub fn set_display_pid(self, display: bool) -> Self {
+        self.set_display(&[JsonField::Pid], display)
+    }
+
+    fn set_display(mut self, fields: &[JsonField], display: bool) -> Self {
+        self.fields.retain(|(f, _)| !fields.contains(f));
+        if display {
+            //pid before thread_id before thread_name, whichever is set first
+            for x in fields {
+                let index = self
+                    .fields
+                    .iter()
+                    .position(|(f, _)| display_order(*f) > display_order(*x))
+                    .unwrap_or(self.fields.len());
+                self.fields.insert(index, (*x, x.key().to_string()));
+            }
+        }
+        self
+    }
+
+    fn write_field(&self, buf: &mut String, field: JsonField, arg: &FastLogRecord) {
+        match field {
+            JsonField::Args => write_json_string(buf, &arg.args),
+            JsonField::Date => self.write_date(buf, arg),
+            JsonField::File => {
+                if arg.file.contains('\\') {
+                    write_json_string(buf, &arg.file.replace('\\', "/"));
+                } else {
+                    write_json_string(buf, &arg.file);
+                }
+            }
+            JsonField::Level => {
+                let _ = write!(buf, "\"{}\"", arg.level);
+            }
+            JsonField::Line => {
+                let _ = write!(buf, "{}", arg.line.unwrap_or_default());
+            }
+            JsonField::Target => write_json_string(buf, &arg.target),
+            JsonField::Module => write_json_string(buf, &arg.module_path),
+            JsonField::Pid => {
+                let _ = write!(buf, "{}", arg.pid);
+            }
+            JsonField::ThreadId => {
+                let _ = write!(buf, "{}", arg.thread_id);
+            }
+            JsonField::ThreadName => {
+                write_json_string(buf, arg.thread_name.as_deref().unwrap_or_default())
+            }
+        }
+    }
+
+    fn write_date(&self, buf: &mut String, arg: &FastLogRecord) {
+        let _ = match self.json_time {
+            JsonTime::Stand => {
                 let now = match self.time_type {
                     TimeType::Local => fastdate::DateTime::from(arg.now)
                         .add_sub_sec(fastdate::offset_sec() as i64)
                         .display_stand(),
                     TimeType::Utc => fastdate::DateTime::from(arg.now).display_stand(),
                 };
-                //{"args":"Commencing yak shaving","date":"2022-08-19 09:53:47.798674","file":"example/src/split_log.rs","level":"INFO","line":21}
-                let args = arg.args.replace("\"", "\\\"");
-                let file = arg.file.replace("\\", "/");
-                arg.formated = format!(
-                    "{}\"args\":\"{}\",\"date\":\"{}\",\"file\":\"{}\",\"level\":\"{}\",\"line\":{}{}",
-                    "{",
-                    args,
-                    now,
-                    file,
-                    arg.level,
-                    arg.line.unwrap_or_default(),
-                    "}\n"
-                );
+                write!(buf, "\"{}\"", now)
+            }
+            JsonTime::Rfc3339 => {
+                buf.push('"');
+                write_rfc3339(buf, arg, &self.time_type);
+                buf.write_str("\"")
+            }
+            JsonTime::EpochMillis => {
+                let since = arg.now.duration_since(UNIX_EPOCH).unwrap_or_default();
+                write!(buf, "{}", since.as_millis())
+            }
+            JsonTime::EpochSecs => {
+                let since = arg.now.duration_since(UNIX_EPOCH).unwrap_or_default();
+                write!(buf, "{}.{:06}", since.as_secs(), since.subsec_micros())
+            }
+        };
+    }
+}
+
+/// logfmt lines, for example
+/// ts=2022-08-19T09:53:47.798674Z level=info target=split_log msg="Commencing yak shaving" file=example/src/split_log.rs line=21 request_id=abc
+/// values with spaces,'=','"' or control characters are quoted and escaped, context and record fields follow line
+#[derive(Default)]
+pub struct LogfmtFormat {
+    pub time_type: TimeType,
+    // add thread_id and thread_name
+    pub display_thread: bool,
+    // add pid
+    pub display_pid: bool,
+}
+
+impl RecordFormat for LogfmtFormat {
+    fn do_format(&self, arg: &mut FastLogRecord) {
+        match &arg.command {
+            Command::CommandRecord => {
+                let mut buf = std::mem::take(&mut arg.formated);
+                buf.clear();
+                buf.push_str("ts=");
+                write_rfc3339(&mut buf, arg, &self.time_type);
+                let _ = write!(buf, " level={}", arg.level.as_str().to_ascii_lowercase());
+                buf.push_str(" target=");
+                write_logfmt_value(&mut buf, &arg.target);
+                buf.push_str(" msg=");
+                write_logfmt_value(&mut buf, &arg.args);
+                buf.push_str(" file=");
+                write_logfmt_value(&mut buf, &arg.file);
+                let _ = write!(buf, " line={}", arg.line.unwrap_or_default());
+                if self.display_pid {
+                    let _ = write!(buf, " pid={}", arg.pid);
+                }
+                if self.display_thread {
+                    let _ = write!(buf, " thread_id={}", arg.thread_id);
+                    if let Some(name) = &arg.thread_name {
+                        buf.push_str(" thread_name=");
+                        write_logfmt_value(&mut buf, name);
+                    }
+                }
+                if let Some(context) = &arg.context {
+                    write_logfmt_fields(&mut buf, context);
+                }
+                write_logfmt_fields(&mut buf, &arg.fields);
+                buf.push('\n');
+                arg.formated = buf;
             }
             Command::CommandExit => {}
             Command::CommandFlush(_) => {}
@@ -111,8 +404,767 @@ impl RecordFormat for FastLogFormatJson {
     }
 }
 
-impl FastLogFormatJson {
-    pub fn new() -> FastLogFormatJson {
+impl LogfmtFormat {
+    pub fn new() -> LogfmtFormat {
         Self::default()
     }
+
+    /// set time_type
+    pub fn set_time_type(mut self, time_type: TimeType) -> Self {
+        self.time_type = time_type;
+        self
+    }
+
+    /// add thread_id and thread_name of the logging thread
+    pub fn set_display_thread(mut self, display: bool) -> Self {
+        self.display_thread = display;
+        self
+    }
+
+    /// add pid of the logging process
+    pub fn set_display_pid(mut self, display: bool) -> Self {
+        self.display_pid = display;
+        self
+    }
+}
+
+/// write fields as " key=value", keys are made of [A-Za-z0-9_.-:/] and other chars become '_'
+fn write_logfmt_fields(buf: &mut String, fields: &[(String, FieldValue)]) {
+    for (k, v) in fields {
+        buf.push(' ');
+        if k.is_empty() {
+            buf.push('_');
+        }
+        for c in k.chars() {
+            match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' | '-' | ':' | '/' => buf.push(c),
+                _ => buf.push('_'),
+            }
+        }
+        buf.push('=');
+        match v {
+            FieldValue::Str(v) => write_logfmt_value(buf, v),
+            v => {
+                let _ = write!(buf, "{}", v);
+            }
+        }
+    }
+}
+
+/// write an value, quoted and escaped if empty or it has spaces,'=','"' or control characters
+fn write_logfmt_value(buf: &mut String, arg: &str) {
+    let quote = arg.is_empty()
+        || arg
+            .chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+    if !quote {
+        buf.push_str(arg);
+        return;
+    }
+    buf.push('"');
+    write_json_escape(buf, arg);
+    buf.push('"');
+}
+
+/// write the time as 2022-08-19T09:53:47.798674Z, or with the local offset +08:00
+fn write_rfc3339(buf: &mut String, arg: &FastLogRecord, time_type: &TimeType) {
+    let now = match time_type {
+        TimeType::Local => fastdate::DateTime::from(arg.now).set_offset(fastdate::offset_sec()),
+        TimeType::Utc => fastdate::DateTime::from(arg.now),
+    };
+    let _ = write!(
+        buf,
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}",
+        now.year(),
+        now.mon(),
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.sec(),
+        now.nano() / 1000
+    );
+    let offset = now.offset();
+    if offset == 0 {
+        buf.push('Z');
+    } else {
+        let sign = if offset >= 0 { '+' } else { '-' };
+        let offset = offset.abs();
+        let _ = write!(
+            buf,
+            "{}{:02}:{:02}",
+            sign,
+            offset / 3600,
+            offset % 3600 / 60
+        );
+    }
+}
+
+/// syslog facility, the PRI of a message is facility * 8 + severity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Facility {
+    Kern = 0,
+    #[default]
+    User = 1,
+    Mail = 2,
+    Daemon = 3,
+    Auth = 4,
+    Syslog = 5,
+    Lpr = 6,
+    News = 7,
+    Uucp = 8,
+    Cron = 9,
+    AuthPriv = 10,
+    Ftp = 11,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// the syslog message header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyslogProtocol {
+    /// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`
+    Rfc5424,
+    /// `<PRI>Mmm dd hh:mm:ss HOSTNAME TAG[PID]: MSG`
+    Rfc3164,
+}
+
+/// syslog messages for rsyslog and co. for example:
+// fast_log::init(Config::new().format(
+//     SyslogFormat::rfc5424().set_facility(Facility::Local0).set_app_name("yak-shaver"),
+// ).file("target/syslog.log"))
+/// level maps to severity(Error=3,Warn=4,Info=6,Debug and Trace=7).
+/// context and record fields go into the RFC 5424 STRUCTURED-DATA([sd_id key="value"]),
+/// or after the RFC 3164 message as key=value
+pub struct SyslogFormat {
+    pub protocol: SyslogProtocol,
+    pub facility: Facility,
+    /// default $HOSTNAME or /etc/hostname
+    pub hostname: String,
+    /// default the executable name
+    pub app_name: String,
+    /// default None: the pid of the record
+    pub procid: Option<String>,
+    /// RFC 5424 only,default None("-")
+    pub msgid: Option<String>,
+    /// the SD-ID of the fields, default "fields@32473"
+    pub sd_id: String,
+    /// prefix messages with their length(RFC 6587 octet counting) instead of ending them with '\n',
+    /// with '\n' control characters in messages are escaped as #ooo(octal)
+    pub octet_counting: bool,
+    pub time_type: TimeType,
+}
+
+impl SyslogFormat {
+    pub fn new(protocol: SyslogProtocol) -> Self {
+        let hostname = std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .unwrap_or_else(|| "localhost".to_string());
+        let app_name = std::env::current_exe()
+            .ok()
+            .and_then(|x| x.file_stem().map(|x| x.to_string_lossy().to_string()))
+            .unwrap_or_else(|| "-".to_string());
+        Self {
+            protocol,
+            facility: Facility::default(),
+            hostname,
+            app_name,
+            procid: None,
+            msgid: None,
+            sd_id: "fields@32473".to_string(),
+            octet_counting: false,
+            time_type: TimeType::default(),
+        }
+    }
+
+    pub fn rfc5424() -> Self {
+        Self::new(SyslogProtocol::Rfc5424)
+    }
+
+    pub fn rfc3164() -> Self {
+        Self::new(SyslogProtocol::Rfc3164)
+    }
+
+    pub fn set_facility(mut self, facility: Facility) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    pub fn set_hostname(mut self, hostname: &str) -> Self {
+        self.hostname = hostname.to_string();
+        self
+    }
+
+    pub fn set_app_name(mut self, app_name: &str) -> Self {
+        self.app_name = app_name.to_string();
+        self
+    }
+
+    pub fn set_procid(mut self, procid: &str) -> Self {
+        self.procid = Some(procid.to_string());
+        self
+    }
+
+    pub fn set_msgid(mut self, msgid: &str) -> Self {
+        self.msgid = Some(msgid.to_string());
+        self
+    }
+
+    pub fn set_sd_id(mut self, sd_id: &str) -> Self {
+        self.sd_id = sd_id.to_string();
+        self
+    }
+
+    pub fn set_octet_counting(mut self, octet_counting: bool) -> Self {
+        self.octet_counting = octet_counting;
+        self
+    }
+
+    /// set time_type
+    pub fn set_time_type(mut self, time_type: TimeType) -> Self {
+        self.time_type = time_type;
+        self
+    }
+
+    fn write_rfc5424(&self, buf: &mut String, arg: &FastLogRecord) {
+        buf.push_str("1 ");
+        write_rfc3339(buf, arg, &self.time_type);
+        buf.push(' ');
+        write_header_field(buf, &self.hostname, 255);
+        buf.push(' ');
+        write_header_field(buf, &self.app_name, 48);
+        buf.push(' ');
+        match &self.procid {
+            Some(procid) => write_header_field(buf, procid, 128),
+            None => {
+                let _ = write!(buf, "{}", arg.pid);
+            }
+        }
+        buf.push(' ');
+        write_header_field(buf, self.msgid.as_deref().unwrap_or_default(), 32);
+        buf.push(' ');
+        let context = arg.context.as_deref().map(|x| x.as_slice());
+        let fields = context.unwrap_or_default().iter().chain(arg.fields.iter());
+        let mut has_fields = false;
+        for (k, v) in fields {
+            if !has_fields {
+                buf.push('[');
+                write_header_field(buf, &self.sd_id, 32);
+                has_fields = true;
+            }
+            buf.push(' ');
+            //PARAM-NAME: printable ascii except '=',' ',']','"'
+            for c in k.chars().take(32) {
+                match c {
+                    '=' | ' ' | ']' | '"' => buf.push('_'),
+                    c if c.is_ascii_graphic() => buf.push(c),
+                    _ => buf.push('_'),
+                }
+            }
+            buf.push_str("=\"");
+            let value = v.to_string();
+            for c in value.chars() {
+                match c {
+                    '"' | '\\' | ']' => {
+                        buf.push('\\');
+                        buf.push(c);
+                    }
+                    c => buf.push(c),
+                }
+            }
+            buf.push('"');
+        }
+        if has_fields {
+            buf.push(']');
+        } else {
+            buf.push('-');
+        }
+        if !arg.args.is_empty() {
+            buf.push(' ');
+            self.write_msg(buf, &arg.args);
+        }
+    }
+
+    fn write_rfc3164(&self, buf: &mut String, arg: &FastLogRecord) {
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        let now = match self.time_type {
+            TimeType::Local => fastdate::DateTime::from(arg.now).set_offset(fastdate::offset_sec()),
+            TimeType::Utc => fastdate::DateTime::from(arg.now),
+        };
+        let _ = write!(
+            buf,
+            "{} {:>2} {:02}:{:02}:{:02} ",
+            MONTHS[(now.mon() as usize).clamp(1, 12) - 1],
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.sec()
+        );
+        write_header_field(buf, &self.hostname, 255);
+        buf.push(' ');
+        //TAG: at most 32 alphanumeric characters
+        let start = buf.len();
+        buf.extend(
+            self.app_name
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_' || *c == '.')
+                .take(32),
+        );
+        if buf.len() == start {
+            buf.push('-');
+        }
+        match &self.procid {
+            Some(procid) => {
+                buf.push('[');
+                write_header_field(buf, procid, 128);
+                buf.push(']');
+            }
+            None => {
+                let _ = write!(buf, "[{}]", arg.pid);
+            }
+        }
+        buf.push_str(": ");
+        let mut msg = String::new();
+        msg.push_str(&arg.args);
+        if let Some(context) = &arg.context {
+            write_fields_text(&mut msg, context);
+        }
+        write_fields_text(&mut msg, &arg.fields);
+        self.write_msg(buf, &msg);
+    }
+
+    /// with newline framing control characters are escaped as #ooo, like rsyslog does
+    fn write_msg(&self, buf: &mut String, msg: &str) {
+        if self.octet_counting {
+            buf.push_str(msg);
+            return;
+        }
+        for c in msg.chars() {
+            if c.is_ascii_control() {
+                let _ = write!(buf, "#{:03o}", c as u32);
+            } else {
+                buf.push(c);
+            }
+        }
+    }
+}
+
+impl RecordFormat for SyslogFormat {
+    fn do_format(&self, arg: &mut FastLogRecord) {
+        match &arg.command {
+            Command::CommandRecord => {
+                let severity = match arg.level {
+                    log::Level::Error => 3,
+                    log::Level::Warn => 4,
+                    log::Level::Info => 6,
+                    log::Level::Debug => 7,
+                    log::Level::Trace => 7,
+                };
+                let mut buf = std::mem::take(&mut arg.formated);
+                buf.clear();
+                let _ = write!(buf, "<{}>", self.facility as u8 * 8 + severity);
+                match self.protocol {
+                    SyslogProtocol::Rfc5424 => self.write_rfc5424(&mut buf, arg),
+                    SyslogProtocol::Rfc3164 => self.write_rfc3164(&mut buf, arg),
+                }
+                if self.octet_counting {
+                    let len = buf.len().to_string();
+                    buf.insert(0, ' ');
+                    buf.insert_str(0, &len);
+                } else {
+                    buf.push('\n');
+                }
+                arg.formated = buf;
+            }
+            Command::CommandExit => {}
+            Command::CommandFlush(_) => {}
+        }
+    }
+}
+
+/// write an syslog header field: printable ascii, at most max chars, "-" if empty
+fn write_header_field(buf: &mut String, arg: &str, max: usize) {
+    if arg.is_empty() {
+        buf.push('-');
+        return;
+    }
+    for c in arg.chars().take(max) {
+        if c.is_ascii_graphic() {
+            buf.push(c);
+        } else {
+            buf.push('_');
+        }
+    }
+}
+
+/// formats records by a pattern parsed once at construction, for example
+/// "%d{%H:%M:%S%.3f} %-5l %t [%f:%L] %m%n". placeholders:
+/// * %d time(same as FastLogFormat), %d{..} time by %Y %m %d %H %M %S %.3f %.6f %.9f %z %:z
+/// * %l level, %t target, %M module, %f file, %L line, %T thread(name:id), %P pid
+/// * %m message, %F context and record fields(key=value), %n newline, %% a '%'
+///
+/// a width after % pads the placeholder: %5l aligns right, %-5l aligns left
+pub struct PatternFormat {
+    pieces: Vec<Piece>,
+    pub time_type: TimeType,
+}
+
+struct Piece {
+    kind: PieceKind,
+    width: usize,
+    left: bool,
+}
+
+enum PieceKind {
+    Literal(String),
+    Time(Option<Vec<TimePiece>>),
+    Level,
+    Target,
+    Module,
+    File,
+    Line,
+    Thread,
+    Pid,
+    Message,
+    Fields,
+}
+
+enum TimePiece {
+    Literal(String),
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    /// fraction of the second with 3, 6 or 9 digits
+    Fraction(usize),
+    /// +0800, or +08:00 if true
+    Offset(bool),
+}
+
+impl PatternFormat {
+    /// parse the pattern, an unknown placeholder is an error
+    pub fn new(pattern: &str) -> Result<Self, LogError> {
+        let mut pieces = vec![];
+        let mut literal = String::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                literal.push(c);
+                continue;
+            }
+            let left = chars.next_if_eq(&'-').is_some();
+            let mut width = 0;
+            while let Some(d) = chars.next_if(|x| x.is_ascii_digit()) {
+                width = width * 10 + d.to_digit(10).unwrap_or_default() as usize;
+            }
+            let kind = match chars.next() {
+                Some('%') => {
+                    literal.push('%');
+                    continue;
+                }
+                Some('n') => {
+                    literal.push('\n');
+                    continue;
+                }
+                Some('d') => {
+                    if chars.next_if_eq(&'{').is_some() {
+                        let mut time = String::new();
+                        loop {
+                            match chars.next() {
+                                Some('}') => break,
+                                Some(c) => time.push(c),
+                                None => {
+                                    return Err(LogError::from(format!(
+                                        "[fast_log] unclosed %d{{ in pattern: {}",
+                                        pattern
+                                    )))
+                                }
+                            }
+                        }
+                        PieceKind::Time(Some(parse_time(&time)?))
+                    } else {
+                        PieceKind::Time(None)
+                    }
+                }
+                Some('l') => PieceKind::Level,
+                Some('t') => PieceKind::Target,
+                Some('M') => PieceKind::Module,
+                Some('f') => PieceKind::File,
+                Some('L') => PieceKind::Line,
+                Some('T') => PieceKind::Thread,
+                Some('P') => PieceKind::Pid,
+                Some('m') => PieceKind::Message,
+                Some('F') => PieceKind::Fields,
+                Some(c) => {
+                    return Err(LogError::from(format!(
+                        "[fast_log] unknown placeholder %{} in pattern: {}",
+                        c, pattern
+                    )))
+                }
+                None => {
+                    return Err(LogError::from(format!(
+                        "[fast_log] pattern ends with %: {}",
+                        pattern
+                    )))
+                }
+            };
+            if !literal.is_empty() {
+                pieces.push(Piece {
+                    kind: PieceKind::Literal(std::mem::take(&mut literal)),
+                    width: 0,
+                    left: false,
+                });
+            }
+            pieces.push(Piece { kind, width, left });
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece {
+                kind: PieceKind::Literal(literal),
+                width: 0,
+                left: false,
+            });
+        }
+        Ok(Self {
+            pieces,
+            time_type: TimeType::default(),
+        })
+    }
+
+    /// set time_type
+    pub fn set_time_type(mut self, time_type: TimeType) -> Self {
+        self.time_type = time_type;
+        self
+    }
+
+    fn write_piece(&self, buf: &mut String, kind: &PieceKind, arg: &FastLogRecord) {
+        match kind {
+            PieceKind::Literal(v) => buf.push_str(v),
+            PieceKind::Time(time) => {
+                let now = match self.time_type {
+                    TimeType::Local => {
+                        fastdate::DateTime::from(arg.now).set_offset(fastdate::offset_sec())
+                    }
+                    TimeType::Utc => fastdate::DateTime::from(arg.now),
+                };
+                match time {
+                    None => buf.push_str(&now.display_stand()),
+                    Some(time) => write_time(buf, time, &now),
+                }
+            }
+            PieceKind::Level => {
+                let _ = write!(buf, "{}", arg.level);
+            }
+            PieceKind::Target => buf.push_str(&arg.target),
+            PieceKind::Module => buf.push_str(&arg.module_path),
+            PieceKind::File => buf.push_str(&arg.file),
+            PieceKind::Line => {
+                let _ = write!(buf, "{}", arg.line.unwrap_or_default());
+            }
+            PieceKind::Thread => match &arg.thread_name {
+                Some(name) => {
+                    let _ = write!(buf, "{}:{}", name, arg.thread_id);
+                }
+                None => {
+                    let _ = write!(buf, "{}", arg.thread_id);
+                }
+            },
+            PieceKind::Pid => {
+                let _ = write!(buf, "{}", arg.pid);
+            }
+            PieceKind::Message => buf.push_str(&arg.args),
+            PieceKind::Fields => {
+                let start = buf.len();
+                if let Some(context) = &arg.context {
+                    write_fields_text(buf, context);
+                }
+                write_fields_text(buf, &arg.fields);
+                //no leading space
+                if buf.len() > start {
+                    buf.remove(start);
+                }
+            }
+        }
+    }
+}
+
+impl RecordFormat for PatternFormat {
+    fn do_format(&self, arg: &mut FastLogRecord) {
+        match &arg.command {
+            Command::CommandRecord => {
+                let mut buf = std::mem::take(&mut arg.formated);
+                buf.clear();
+                for piece in &self.pieces {
+                    let start = buf.len();
+                    self.write_piece(&mut buf, &piece.kind, arg);
+                    let len = buf[start..].chars().count();
+                    if len < piece.width {
+                        let pad = " ".repeat(piece.width - len);
+                        if piece.left {
+                            buf.push_str(&pad);
+                        } else {
+                            buf.insert_str(start, &pad);
+                        }
+                    }
+                }
+                arg.formated = buf;
+            }
+            Command::CommandExit => {}
+            Command::CommandFlush(_) => {}
+        }
+    }
+}
+
+fn parse_time(pattern: &str) -> Result<Vec<TimePiece>, LogError> {
+    let mut pieces = vec![];
+    let mut literal = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            literal.push(c);
+            continue;
+        }
+        let piece = match chars.next() {
+            Some('%') => {
+                literal.push('%');
+                continue;
+            }
+            Some('Y') => TimePiece::Year,
+            Some('m') => TimePiece::Month,
+            Some('d') => TimePiece::Day,
+            Some('H') => TimePiece::Hour,
+            Some('M') => TimePiece::Minute,
+            Some('S') => TimePiece::Second,
+            Some('z') => TimePiece::Offset(false),
+            Some(':') if chars.next() == Some('z') => TimePiece::Offset(true),
+            Some('.') => match (chars.next(), chars.next()) {
+                (Some('3'), Some('f')) => TimePiece::Fraction(3),
+                (Some('6'), Some('f')) => TimePiece::Fraction(6),
+                (Some('9'), Some('f')) => TimePiece::Fraction(9),
+                _ => {
+                    return Err(LogError::from(format!(
+                        "[fast_log] unknown time fraction in: {}",
+                        pattern
+                    )))
+                }
+            },
+            _ => {
+                return Err(LogError::from(format!(
+                    "[fast_log] unknown time placeholder in: {}",
+                    pattern
+                )))
+            }
+        };
+        if !literal.is_empty() {
+            pieces.push(TimePiece::Literal(std::mem::take(&mut literal)));
+        }
+        pieces.push(piece);
+    }
+    if !literal.is_empty() {
+        pieces.push(TimePiece::Literal(literal));
+    }
+    Ok(pieces)
+}
+
+fn write_time(buf: &mut String, pieces: &[TimePiece], now: &fastdate::DateTime) {
+    for piece in pieces {
+        let _ = match piece {
+            TimePiece::Literal(v) => buf.write_str(v),
+            TimePiece::Year => write!(buf, "{:04}", now.year()),
+            TimePiece::Month => write!(buf, "{:02}", now.mon()),
+            TimePiece::Day => write!(buf, "{:02}", now.day()),
+            TimePiece::Hour => write!(buf, "{:02}", now.hour()),
+            TimePiece::Minute => write!(buf, "{:02}", now.minute()),
+            TimePiece::Second => write!(buf, "{:02}", now.sec()),
+            TimePiece::Fraction(3) => write!(buf, ".{:03}", now.nano() / 1_000_000),
+            TimePiece::Fraction(6) => write!(buf, ".{:06}", now.nano() / 1_000),
+            TimePiece::Fraction(_) => write!(buf, ".{:09}", now.nano()),
+            TimePiece::Offset(colon) => {
+                let offset = now.offset();
+                let sign = if offset >= 0 { '+' } else { '-' };
+                let offset = offset.abs();
+                if *colon {
+                    write!(
+                        buf,
+                        "{}{:02}:{:02}",
+                        sign,
+                        offset / 3600,
+                        offset % 3600 / 60
+                    )
+                } else {
+                    write!(buf, "{}{:02}{:02}", sign, offset / 3600, offset % 3600 / 60)
+                }
+            }
+        };
+    }
+}
+
+/// write fields as " key=value key=value"
+fn write_fields_text(buf: &mut String, fields: &[(String, FieldValue)]) {
+    for (k, v) in fields {
+        let _ = write!(buf, " {}={}", k, v);
+    }
+}
+
+/// write fields as json members ,"key":value
+fn write_json_fields(buf: &mut String, fields: &[(String, FieldValue)]) {
+    for (k, v) in fields {
+        write_json_member(buf, k, v);
+    }
+}
+
+/// write an json member ,"key":value(no ',' right after the '{')
+fn write_json_member(buf: &mut String, key: &str, value: &FieldValue) {
+    if !buf.ends_with('{') {
+        buf.push(',');
+    }
+    write_json_string(buf, key);
+    buf.push(':');
+    write_json_value(buf, value);
+}
+
+/// write an escaped json string with quotes
+fn write_json_string(buf: &mut String, arg: &str) {
+    buf.push('"');
+    write_json_escape(buf, arg);
+    buf.push('"');
+}
+
+/// write an escaped json string(without quotes)
+fn write_json_escape(buf: &mut String, arg: &str) {
+    for c in arg.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(buf, "\\u{:04x}", c as u32);
+            }
+            c => buf.push(c),
+        }
+    }
+}
+
+/// write an field value as json value
+fn write_json_value(buf: &mut String, value: &FieldValue) {
+    match value {
+        FieldValue::Str(v) => write_json_string(buf, v),
+        FieldValue::F64(v) if !v.is_finite() => buf.push_str("null"),
+        v => {
+            let _ = write!(buf, "{}", v);
+        }
+    }
 }
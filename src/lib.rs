@@ -4,12 +4,18 @@ pub mod appender;
 pub mod bencher;
 pub mod config;
 pub mod consts;
+pub mod context;
 pub mod error;
 pub mod fast_log;
 pub mod filter;
 pub mod formats;
+pub mod metrics;
 pub mod plugin;
+pub mod pool;
 pub mod runtime;
+pub mod suppress;
+#[cfg(feature = "tracing")]
+pub mod tracing;
 
 pub use crate::config::Config;
 pub use crate::fast_log::*;
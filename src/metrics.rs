@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// counters an appender keeps by itself and shares with the pipeline,
+/// see LogAppender::counters
+#[derive(Debug, Default)]
+pub struct AppenderCounters {
+    /// log files rotated(split)
+    pub rotations: AtomicU64,
+    /// packs finished
+    pub packs: AtomicU64,
+    /// packs failed
+    pub pack_failures: AtomicU64,
+}
+
+/// counters of one appender thread
+#[derive(Debug, Default)]
+pub struct AppenderMetrics {
+    pub records: AtomicU64,
+    pub bytes: AtomicU64,
+    pub batches: AtomicU64,
+    pub batch_nanos: AtomicU64,
+    pub max_batch_nanos: AtomicU64,
+    /// failed do_logs calls(retries included)
+    pub errors: AtomicU64,
+    pub counters: Option<Arc<AppenderCounters>>,
+}
+
+impl AppenderMetrics {
+    pub fn new(counters: Option<Arc<AppenderCounters>>) -> Self {
+        Self {
+            counters,
+            ..Default::default()
+        }
+    }
+
+    /// record one do_logs call
+    pub fn add_batch(&self, records: u64, bytes: u64, cost: Duration) {
+        let nanos = cost.as_nanos() as u64;
+        self.records.fetch_add(records, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.batch_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_batch_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self, queue_len: usize) -> AppenderSnapshot {
+        let batches = self.batches.load(Ordering::Relaxed);
+        let batch_nanos = self.batch_nanos.load(Ordering::Relaxed);
+        let mut snapshot = AppenderSnapshot {
+            queue_len,
+            records: self.records.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            batches,
+            avg_batch_latency: Duration::from_nanos(batch_nanos.checked_div(batches).unwrap_or(0)),
+            max_batch_latency: Duration::from_nanos(self.max_batch_nanos.load(Ordering::Relaxed)),
+            errors: self.errors.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+        if let Some(counters) = &self.counters {
+            snapshot.rotations = counters.rotations.load(Ordering::Relaxed);
+            snapshot.packs = counters.packs.load(Ordering::Relaxed);
+            snapshot.pack_failures = counters.pack_failures.load(Ordering::Relaxed);
+        }
+        snapshot
+    }
+}
+
+/// counters of a running pipeline
+#[derive(Debug)]
+pub struct Metrics {
+    pub start: Instant,
+    /// records accepted into the main channel
+    pub records: AtomicU64,
+    /// records dropped by OverflowPolicy
+    pub dropped: AtomicU64,
+    pub appenders: Vec<AppenderMetrics>,
+}
+
+impl Metrics {
+    pub fn new(appenders: Vec<AppenderMetrics>) -> Self {
+        Self {
+            start: Instant::now(),
+            records: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            appenders,
+        }
+    }
+}
+
+/// a point-in-time copy of the pipeline metrics, see Logger::metrics
+#[derive(Clone, Debug, Default)]
+pub struct MetricsSnapshot {
+    /// time since the pipeline started
+    pub uptime: Duration,
+    /// records waiting in the main channel
+    pub queue_len: usize,
+    /// records accepted into the main channel
+    pub records: u64,
+    /// records accepted per second since start
+    pub records_per_sec: f64,
+    /// records dropped by OverflowPolicy
+    pub dropped: u64,
+    /// in the order of Config.appends
+    pub appenders: Vec<AppenderSnapshot>,
+}
+
+/// a point-in-time copy of one appender metrics
+#[derive(Clone, Debug, Default)]
+pub struct AppenderSnapshot {
+    /// batches waiting in the appender channel
+    pub queue_len: usize,
+    /// records written by do_logs
+    pub records: u64,
+    /// formatted bytes written by do_logs
+    pub bytes: u64,
+    /// batches written by do_logs
+    pub batches: u64,
+    pub avg_batch_latency: Duration,
+    pub max_batch_latency: Duration,
+    /// failed do_logs calls(retries included),see Config::error_handler
+    pub errors: u64,
+    pub rotations: u64,
+    pub packs: u64,
+    pub pack_failures: u64,
+}
@@ -0,0 +1,72 @@
+use crate::appender::{AsyncLogAppender, Command, FastLogRecord, LogAppender};
+use crate::error::LogError;
+use crate::{chan, Receiver, Sender, RUNTIME};
+use std::cell::RefCell;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+
+/// runs an AsyncLogAppender as a task of a tokio runtime(fast_log's RUNTIME by default).
+/// the pipeline hands each batch to the task without copying it,
+/// flush and exit wait until the task has written the batches before them
+pub struct AsyncAppender {
+    sender: RefCell<Option<tokio::sync::mpsc::Sender<Arc<Vec<FastLogRecord>>>>>,
+    /// disconnected when the task is done
+    done: Receiver<()>,
+}
+
+impl AsyncAppender {
+    /// spawn the task on fast_log's RUNTIME, so a flush that blocks a thread of the
+    /// application runtime(for example a current_thread runtime) never waits on itself
+    pub fn new<A: AsyncLogAppender>(appender: A) -> Self {
+        Self::with_handle(appender, RUNTIME.handle())
+    }
+
+    /// spawn the task on the runtime of handle.
+    /// flush and shutdown block until the task has run, so do not call them from that runtime
+    /// unless it has another worker free(tokio::task::block_in_place on a multi_thread runtime)
+    pub fn with_handle<A: AsyncLogAppender>(appender: A, handle: &Handle) -> Self {
+        let (sender, mut recv) = tokio::sync::mpsc::channel::<Arc<Vec<FastLogRecord>>>(64);
+        let (done_send, done) = chan::<()>(Some(1));
+        handle.spawn(async move {
+            //dropped when the task ends
+            let _done: Sender<()> = done_send;
+            while let Some(records) = recv.recv().await {
+                appender.do_logs(&records).await;
+            }
+        });
+        Self {
+            sender: RefCell::new(Some(sender)),
+            done,
+        }
+    }
+
+    fn send(&self, records: Arc<Vec<FastLogRecord>>) -> Result<(), LogError> {
+        let exit = records.iter().any(|x| x.command == Command::CommandExit);
+        let result = match self.sender.borrow().as_ref() {
+            Some(sender) => sender
+                .blocking_send(records)
+                .map_err(|_| LogError::from("[fast_log] async appender task stopped")),
+            None => Err(LogError::from("[fast_log] async appender exited")),
+        };
+        if exit {
+            //the task ends after the last batch, wait for it
+            self.sender.borrow_mut().take();
+            let _ = self.done.recv();
+        }
+        result
+    }
+}
+
+impl LogAppender for AsyncAppender {
+    fn do_logs(&self, records: &[FastLogRecord]) {
+        let _ = self.try_do_logs(records);
+    }
+
+    fn try_do_logs(&self, records: &[FastLogRecord]) -> Result<(), LogError> {
+        self.send(Arc::new(records.to_vec()))
+    }
+
+    fn do_logs_shared(&self, records: &Arc<Vec<FastLogRecord>>) -> Result<(), LogError> {
+        self.send(records.clone())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::appender::{Command, FastLogRecord, LogAppender};
+use crate::config::Config;
+use crate::error::LogError;
+use crate::fast_log::Logger;
+use log::{Level, LevelFilter};
+use parking_lot::{Condvar, Mutex};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// keeps the records(raw and formatted) in memory, for asserting on log output in tests.
+/// clones share the same records, keep one and add the other to the Config. for example:
+// let capture = CaptureAppender::new();
+// let logger = Logger::new(Config::new().custom(capture.clone())).unwrap();
+// log::info!(logger: logger, "Commencing yak shaving");
+// assert!(capture.wait_for(1, Duration::from_secs(1)));
+// capture.assert_contains(Level::Info, "", "yak");
+#[derive(Clone, Default)]
+pub struct CaptureAppender {
+    inner: Arc<Captured>,
+}
+
+#[derive(Default)]
+struct Captured {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    records: Vec<FastLogRecord>,
+    /// flush commands received
+    flushes: usize,
+}
+
+impl CaptureAppender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a standalone Logger of config writing to a new CaptureAppender.
+    /// raises log::max_level to Trace, so only the levels of config filter records
+    pub fn logger(config: Config) -> Result<(Logger, Self), LogError> {
+        log::set_max_level(LevelFilter::Trace);
+        let capture = Self::new();
+        let logger = Logger::new(config.custom(capture.clone()))?;
+        Ok((logger, capture))
+    }
+
+    /// the captured records
+    pub fn records(&self) -> Vec<FastLogRecord> {
+        self.inner.state.lock().records.clone()
+    }
+
+    /// the formatted records
+    pub fn lines(&self) -> Vec<String> {
+        self.inner
+            .state
+            .lock()
+            .records
+            .iter()
+            .map(|x| x.formated.clone())
+            .collect()
+    }
+
+    /// the messages(args) of the records
+    pub fn messages(&self) -> Vec<String> {
+        self.inner
+            .state
+            .lock()
+            .records
+            .iter()
+            .map(|x| x.args.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.state.lock().records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// how many flush commands(Logger::flush, flush_level, flush_interval) were received
+    pub fn flushes(&self) -> usize {
+        self.inner.state.lock().flushes
+    }
+
+    /// drop the captured records
+    pub fn clear(&self) {
+        self.inner.state.lock().records.clear();
+    }
+
+    /// wait until at least n records are captured, false on timeout
+    pub fn wait_for(&self, n: usize, timeout: Duration) -> bool {
+        self.wait_until(timeout, |state| state.records.len() >= n)
+    }
+
+    /// wait until at least n flush commands are received, false on timeout
+    pub fn wait_for_flush(&self, n: usize, timeout: Duration) -> bool {
+        self.wait_until(timeout, |state| state.flushes >= n)
+    }
+
+    fn wait_until(&self, timeout: Duration, done: impl Fn(&State) -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.inner.state.lock();
+        while !done(&state) {
+            if self
+                .inner
+                .changed
+                .wait_until(&mut state, deadline)
+                .timed_out()
+            {
+                return done(&state);
+            }
+        }
+        true
+    }
+
+    /// true if a record has the level, a target starting with target
+    /// and a message(args) containing message
+    pub fn contains(&self, level: Level, target: &str, message: &str) -> bool {
+        self.inner
+            .state
+            .lock()
+            .records
+            .iter()
+            .any(|x| is_match(x, level, target, message))
+    }
+
+    /// panic with the captured lines if no record matches, see contains
+    #[track_caller]
+    pub fn assert_contains(&self, level: Level, target: &str, message: &str) {
+        if !self.contains(level, target, message) {
+            panic!(
+                "no record matches level={} target={:?} message={:?}, captured:\n{}",
+                level,
+                target,
+                message,
+                self.lines().concat()
+            );
+        }
+    }
+}
+
+fn is_match(x: &FastLogRecord, level: Level, target: &str, message: &str) -> bool {
+    x.level == level && x.target.starts_with(target) && x.args.contains(message)
+}
+
+impl LogAppender for CaptureAppender {
+    fn do_logs(&self, records: &[FastLogRecord]) {
+        let mut state = self.inner.state.lock();
+        let mut changed = false;
+        for x in records {
+            match x.command {
+                Command::CommandRecord => state.records.push(x.clone()),
+                Command::CommandFlush(_) => state.flushes += 1,
+                Command::CommandExit => continue,
+            }
+            changed = true;
+        }
+        if changed {
+            self.inner.changed.notify_all();
+        }
+    }
+}
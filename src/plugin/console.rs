@@ -1,17 +1,42 @@
 use crate::appender::{FastLogRecord, LogAppender};
+use crate::error::LogError;
+use std::io::Write;
 
 /// only write append into console
 pub struct ConsoleAppender {}
 
 impl LogAppender for ConsoleAppender {
     fn do_logs(&self, records: &[FastLogRecord]) {
+        let _ = self.try_do_logs(records);
+    }
+
+    fn try_do_logs(&self, records: &[FastLogRecord]) -> Result<(), LogError> {
         if records.len() == 0 {
-            return;
+            return Ok(());
         }
         let mut buffer = String::with_capacity(records.len());
         for x in records {
             buffer.push_str(&x.formated);
         }
-        print!("{}", buffer);
+        std::io::stdout().lock().write_all(buffer.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// only write append into stderr,for example the Config::fallback of file appenders
+pub struct StderrAppender {}
+
+impl LogAppender for StderrAppender {
+    fn do_logs(&self, records: &[FastLogRecord]) {
+        let _ = self.try_do_logs(records);
+    }
+
+    fn try_do_logs(&self, records: &[FastLogRecord]) -> Result<(), LogError> {
+        let mut buffer = String::with_capacity(records.len());
+        for x in records {
+            buffer.push_str(&x.formated);
+        }
+        std::io::stderr().lock().write_all(buffer.as_bytes())?;
+        Ok(())
     }
 }
@@ -29,6 +29,10 @@ impl FileAppender {
 
 impl LogAppender for FileAppender {
     fn do_logs(&self, records: &[FastLogRecord]) {
+        let _ = self.try_do_logs(records);
+    }
+
+    fn try_do_logs(&self, records: &[FastLogRecord]) -> Result<(), LogError> {
         let mut log_file = self.file.borrow_mut();
         let mut buf = String::new();
         for x in records {
@@ -37,12 +41,13 @@ impl LogAppender for FileAppender {
                 Command::CommandRecord => {}
                 Command::CommandExit => {}
                 Command::CommandFlush(_) => {
-                    let _ = log_file.write_all(buf.as_bytes());
-                    let _ = log_file.flush();
+                    log_file.write_all(buf.as_bytes())?;
+                    log_file.flush()?;
                     buf.clear();
                 }
             }
         }
-        let _ = log_file.write_all(buf.as_bytes());
+        log_file.write_all(buf.as_bytes())?;
+        Ok(())
     }
 }
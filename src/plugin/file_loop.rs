@@ -1,8 +1,10 @@
 use crate::appender::{FastLogRecord, LogAppender};
 use crate::consts::LogSize;
 use crate::error::LogError;
+use crate::metrics::AppenderCounters;
 use crate::plugin::file_split::{FileSplitAppender, KeepType, SplitFile};
 use crate::plugin::packer::LogPacker;
+use std::sync::Arc;
 
 /// Single logs are stored in rolling mode by capacity
 pub struct FileLoopAppender<F: SplitFile> {
@@ -26,4 +28,12 @@ impl<F: SplitFile> LogAppender for FileLoopAppender<F> {
     fn do_logs(&self, records: &[FastLogRecord]) {
         self.file.do_logs(records);
     }
+
+    fn try_do_logs(&self, records: &[FastLogRecord]) -> Result<(), LogError> {
+        self.file.try_do_logs(records)
+    }
+
+    fn counters(&self) -> Option<Arc<AppenderCounters>> {
+        self.file.counters()
+    }
 }
@@ -154,11 +154,17 @@ impl SplitFile for MmapFile {
         let _ = self.bytes.borrow_mut().flush();
     }
 
+    fn sync(&self) -> std::io::Result<()> {
+        //msync is synchronous
+        self.bytes.borrow_mut().flush()
+    }
+
     fn len(&self) -> usize {
         self.size.len()
     }
 
+    /// the index of the last written byte
     fn offset(&self) -> usize {
-        self.offset.load(Ordering::Relaxed) as usize
+        self.find_offset()
     }
 }
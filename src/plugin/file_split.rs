@@ -1,10 +1,11 @@
 use crate::appender::{Command, FastLogRecord, LogAppender};
 use crate::consts::LogSize;
 use crate::error::LogError;
+use crate::metrics::AppenderCounters;
 use crate::plugin::file_name::FileName;
-use crate::{chan, Receiver, Sender, WaitGroup};
+use crate::{chan, spawn, JoinHandle, Receiver, Sender, WaitGroup};
 use fastdate::DateTime;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::fs::{DirEntry, File, OpenOptions};
 use std::io::{Seek, SeekFrom, Write};
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -19,6 +20,11 @@ pub trait SplitFile: Send {
     fn write(&self, buf: &[u8]) -> std::io::Result<usize>;
     fn truncate(&self) -> std::io::Result<()>;
     fn flush(&self);
+    /// flush and make sure the data is on disk(fsync),default only flush
+    fn sync(&self) -> std::io::Result<()> {
+        self.flush();
+        Ok(())
+    }
     fn len(&self) -> usize;
     fn offset(&self) -> usize;
 }
@@ -70,6 +76,11 @@ impl SplitFile for RawFile {
         let _ = self.inner.borrow_mut().flush();
     }
 
+    fn sync(&self) -> std::io::Result<()> {
+        self.inner.borrow_mut().flush()?;
+        self.inner.borrow().sync_data()
+    }
+
     fn len(&self) -> usize {
         if let Ok(v) = self.inner.borrow_mut().metadata() {
             v.len() as usize
@@ -136,11 +147,18 @@ pub struct FileSplitAppender<F: SplitFile> {
     file: F,
     packer: Arc<Box<dyn Packer>>,
     dir_path: String,
-    sender: Sender<LogPack>,
+    /// None after exit,the saver thread stops when the sender is dropped
+    sender: RefCell<Option<Sender<LogPack>>>,
+    saver: RefCell<Option<JoinHandle<()>>>,
+    counters: Arc<AppenderCounters>,
+    /// fsync the temp file on flush
+    fsync: bool,
     temp_size: LogSize,
     //cache data
     temp_bytes: AtomicUsize,
     temp_name: String,
+    /// seek_end is done before the first write
+    seeked: Cell<bool>,
 }
 
 impl<F: SplitFile> FileSplitAppender<F> {
@@ -169,31 +187,49 @@ impl<F: SplitFile> FileSplitAppender<F> {
             sp = "/";
         }
         let temp_file = format!("{}{}{}", dir_path, sp, temp_name);
-        let temp_bytes = AtomicUsize::new(0);
         let file = F::new(&temp_file, temp_size)?;
-        let mut offset = file.offset();
-        if offset != 0 {
-            offset += 1;
-        }
-        temp_bytes.store(offset, Ordering::Relaxed);
-        let _ = file.seek(SeekFrom::Start(temp_bytes.load(Ordering::Relaxed) as u64));
         let (sender, receiver) = chan(None);
         let arc_packer = Arc::new(packer);
-        spawn_saver(
+        let counters = Arc::new(AppenderCounters::default());
+        let saver = spawn_saver(
             temp_name.clone(),
             receiver,
             rolling_type,
             arc_packer.clone(),
+            counters.clone(),
         );
-        Ok(Self {
-            temp_bytes,
+        let appender = Self {
+            temp_bytes: AtomicUsize::new(0),
             dir_path: dir_path.to_string(),
             file,
-            sender,
+            sender: RefCell::new(Some(sender)),
+            saver: RefCell::new(Some(saver)),
+            counters,
+            fsync: false,
             temp_size,
             temp_name,
             packer: arc_packer,
-        })
+            seeked: Cell::new(false),
+        };
+        appender.seek_end();
+        Ok(appender)
+    }
+
+    /// seek to the end of the data in the temp file. done again before the first write,
+    /// the previous appender of the file(see Logger::reconfigure) may have written more since new
+    fn seek_end(&self) {
+        let mut offset = self.file.offset();
+        if offset != 0 {
+            offset += 1;
+        }
+        self.temp_bytes.store(offset, Ordering::Relaxed);
+        let _ = self.file.seek(SeekFrom::Start(offset as u64));
+    }
+
+    /// fsync the temp file on flush(default false: flush only hands the data to the OS)
+    pub fn fsync(mut self, fsync: bool) -> Self {
+        self.fsync = fsync;
+        self
     }
     /// send data make an pack,and truncate data when finish.
     pub fn send_pack(&self) {
@@ -205,14 +241,46 @@ impl<F: SplitFile> FileSplitAppender<F> {
         let new_log_name = self.packer.log_name_create(&first_file_path);
         self.file.flush();
         let _ = std::fs::copy(&first_file_path, &new_log_name);
-        let _ = self.sender.send(LogPack {
-            dir: self.dir_path.clone(),
-            new_log_name: new_log_name,
-            wg: None,
-        });
+        self.counters.rotations.fetch_add(1, Ordering::Relaxed);
+        if let Some(sender) = self.sender.borrow().as_ref() {
+            let _ = sender.send(LogPack {
+                dir: self.dir_path.clone(),
+                new_log_name: new_log_name,
+                wg: None,
+            });
+        }
         self.truncate();
     }
 
+    /// flush the temp file, then wait until the saver thread has finished all pending packs.
+    /// no more packs are made after exit
+    pub fn exit(&self) {
+        self.file.flush();
+        //drop sender, the saver stops when all packs are done
+        self.sender.borrow_mut().take();
+        if let Some(saver) = self.saver.borrow_mut().take() {
+            let _ = saver.join();
+        }
+    }
+
+    /// write the buffered records into the temp file, keep the first error in result
+    fn write_temp(&self, temp: &mut String, result: &mut Result<(), LogError>) {
+        if temp.is_empty() {
+            return;
+        }
+        match self.file.write(temp.as_bytes()) {
+            Ok(w) => {
+                self.temp_bytes.fetch_add(w, Ordering::SeqCst);
+            }
+            Err(e) => {
+                if result.is_ok() {
+                    *result = Err(LogError::from(e));
+                }
+            }
+        }
+        temp.clear();
+    }
+
     pub fn truncate(&self) {
         //reset data
         let _ = self.file.truncate();
@@ -233,6 +301,17 @@ impl LogPack {
         if log_file_path.is_empty() {
             return Err(LogError::from("log_file_path.is_empty"));
         }
+        let mut r = Self::open_pack(packer.as_ref(), log_file_path);
+        //retry
+        let mut retry = 0;
+        while r.is_err() && retry < packer.retry() {
+            retry += 1;
+            r = Self::open_pack(packer.as_ref(), log_file_path);
+        }
+        r
+    }
+
+    fn open_pack(packer: &dyn Packer, log_file_path: &str) -> Result<bool, LogError> {
         let log_file = OpenOptions::new()
             .write(true)
             .read(true)
@@ -244,20 +323,7 @@ impl LogPack {
             )));
         }
         //make
-        let r = packer.do_pack(log_file.unwrap(), log_file_path);
-        if r.is_err() && packer.retry() > 0 {
-            let mut retry = 1;
-            while let Err(_packs) = self.do_pack(packer) {
-                retry += 1;
-                if retry > packer.retry() {
-                    break;
-                }
-            }
-        }
-        if let Ok(b) = r {
-            return Ok(b);
-        }
-        return Ok(false);
+        packer.do_pack(log_file.unwrap(), log_file_path)
     }
 }
 
@@ -351,6 +417,15 @@ impl Keep for KeepType {
 
 impl<F: SplitFile> LogAppender for FileSplitAppender<F> {
     fn do_logs(&self, records: &[FastLogRecord]) {
+        let _ = self.try_do_logs(records);
+    }
+
+    fn try_do_logs(&self, records: &[FastLogRecord]) -> Result<(), LogError> {
+        if !self.seeked.replace(true) {
+            self.seek_end();
+        }
+        //keep going on error, flush and exit commands must still be handled
+        let mut result = Ok(());
         //if temp_bytes is full,must send pack
         let mut temp = String::with_capacity(records.len() * 10);
         for x in records {
@@ -361,45 +436,44 @@ impl<F: SplitFile> LogAppender for FileSplitAppender<F> {
                         + x.formated.as_bytes().len())
                         >= self.temp_size.get_len()
                     {
-                        self.temp_bytes.fetch_add(
-                            {
-                                let w = self.file.write(temp.as_bytes());
-                                if let Ok(w) = w {
-                                    w
-                                } else {
-                                    0
-                                }
-                            },
-                            Ordering::SeqCst,
-                        );
-                        temp.clear();
+                        self.write_temp(&mut temp, &mut result);
                         self.send_pack();
                     }
                     temp.push_str(x.formated.as_str());
                 }
                 Command::CommandExit => {}
                 Command::CommandFlush(ref w) => {
-                    let _ = self.sender.send(LogPack {
-                        dir: "".to_string(),
-                        new_log_name: "".to_string(),
-                        wg: Some(w.clone()),
-                    });
+                    //the records before the flush reach the file(msync for MmapFile)
+                    self.write_temp(&mut temp, &mut result);
+                    if self.fsync {
+                        if let Err(e) = self.file.sync() {
+                            if result.is_ok() {
+                                result = Err(LogError::from(e));
+                            }
+                        }
+                    } else {
+                        self.file.flush();
+                    }
+                    //the saver drops the WaitGroup when the packs before it are done
+                    if let Some(sender) = self.sender.borrow().as_ref() {
+                        let _ = sender.send(LogPack {
+                            dir: "".to_string(),
+                            new_log_name: "".to_string(),
+                            wg: Some(w.clone()),
+                        });
+                    }
                 }
             }
         }
-        if !temp.is_empty() {
-            let _ = self.temp_bytes.fetch_add(
-                {
-                    let w = self.file.write(temp.as_bytes());
-                    if let Ok(w) = w {
-                        w
-                    } else {
-                        0
-                    }
-                },
-                Ordering::SeqCst,
-            );
+        self.write_temp(&mut temp, &mut result);
+        if records.iter().any(|x| x.command == Command::CommandExit) {
+            self.exit();
         }
+        result
+    }
+
+    fn counters(&self) -> Option<Arc<AppenderCounters>> {
+        Some(self.counters.clone())
     }
 }
 
@@ -409,19 +483,27 @@ fn spawn_saver<R: Keep + 'static>(
     r: Receiver<LogPack>,
     rolling_type: R,
     packer: Arc<Box<dyn Packer>>,
-) {
-    std::thread::spawn(move || {
+    counters: Arc<AppenderCounters>,
+) -> JoinHandle<()> {
+    spawn(move || {
         loop {
             if let Ok(pack) = r.recv() {
                 if pack.wg.is_some() {
-                    return;
+                    //packs and retention before the flush are done,
+                    //release the flush and keep running
+                    continue;
                 }
                 let log_file_path = pack.new_log_name.clone();
                 //do save pack
-                let remove = pack.do_pack(packer.as_ref());
-                if let Ok(remove) = remove {
-                    if remove {
-                        let _ = std::fs::remove_file(log_file_path);
+                match pack.do_pack(packer.as_ref()) {
+                    Ok(remove) => {
+                        counters.packs.fetch_add(1, Ordering::Relaxed);
+                        if remove {
+                            let _ = std::fs::remove_file(log_file_path);
+                        }
+                    }
+                    Err(_) => {
+                        counters.pack_failures.fetch_add(1, Ordering::Relaxed);
                     }
                 }
                 //do rolling
@@ -430,7 +512,7 @@ fn spawn_saver<R: Keep + 'static>(
                 break;
             }
         }
-    });
+    })
 }
 
 fn get_base_name(path: &str) -> String {
@@ -1,3 +1,6 @@
+#[cfg(feature = "runtime_tokio")]
+pub mod async_appender;
+pub mod capture;
 pub mod console;
 pub mod file;
 pub mod file_loop;
@@ -0,0 +1,48 @@
+use crate::{chan, Receiver, Sender};
+use once_cell::sync::Lazy;
+
+/// the buffers of FastLogRecord.args and FastLogRecord.formated,
+/// taken by Logger::log and the format thread,given back when the record is dropped
+pub static BUFFER_POOL: Lazy<BufferPool> = Lazy::new(|| BufferPool::new(4096, 4096));
+
+/// a bounded pool of String buffers
+pub struct BufferPool {
+    send: Sender<String>,
+    recv: Receiver<String>,
+    /// buffers larger than this are freed instead of kept
+    max_capacity: usize,
+}
+
+impl BufferPool {
+    pub fn new(len: usize, max_capacity: usize) -> Self {
+        let (send, recv) = chan(Some(len));
+        Self {
+            send,
+            recv,
+            max_capacity,
+        }
+    }
+
+    /// an empty buffer, allocated only if the pool is empty
+    pub fn get(&self) -> String {
+        self.recv.try_recv().unwrap_or_default()
+    }
+
+    /// give back a buffer, it is freed if the pool is full
+    pub fn put(&self, mut buf: String) {
+        if buf.capacity() == 0 || buf.capacity() > self.max_capacity {
+            return;
+        }
+        buf.clear();
+        let _ = self.send.try_send(buf);
+    }
+
+    /// buffers waiting in the pool
+    pub fn len(&self) -> usize {
+        self.recv.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recv.is_empty()
+    }
+}
@@ -1,17 +1,21 @@
-#[cfg(feature = "runtime_thread")]
+//! the channels and threads of the pipeline.
+//! runtime_thread runs the loops on OS threads, runtime_tokio(preferred if both are enabled)
+//! runs them on the blocking pool of fast_log's tokio runtime(still one thread per loop,
+//! they block on the channels). the channels are crossbeam for both,
+//! records are sent by the sync log macros from any thread or task.
+
+#[cfg(any(feature = "runtime_thread", feature = "runtime_tokio"))]
 pub type Receiver<T> = crossbeam::channel::Receiver<T>;
-#[cfg(feature = "runtime_thread")]
+#[cfg(any(feature = "runtime_thread", feature = "runtime_tokio"))]
 pub type Sender<T> = crossbeam::channel::Sender<T>;
-#[cfg(feature = "runtime_thread")]
+#[cfg(any(feature = "runtime_thread", feature = "runtime_tokio"))]
 pub type SendError<T> = crossbeam_channel::SendError<T>;
-#[cfg(feature = "runtime_thread")]
+#[cfg(any(feature = "runtime_thread", feature = "runtime_tokio"))]
 pub type RecvError = crossbeam_channel::RecvError;
-#[cfg(feature = "runtime_thread")]
-pub type JoinHandle<T> = std::thread::JoinHandle<T>;
-#[cfg(feature = "runtime_thread")]
+#[cfg(any(feature = "runtime_thread", feature = "runtime_tokio"))]
 pub type WaitGroup = crossbeam_utils::sync::WaitGroup;
 
-#[cfg(feature = "runtime_thread")]
+#[cfg(any(feature = "runtime_thread", feature = "runtime_tokio"))]
 pub fn chan<T>(len: Option<usize>) -> (Sender<T>, Receiver<T>) {
     match len {
         None => crossbeam::channel::unbounded(),
@@ -19,7 +23,10 @@ pub fn chan<T>(len: Option<usize>) -> (Sender<T>, Receiver<T>) {
     }
 }
 
-#[cfg(feature = "runtime_thread")]
+#[cfg(all(feature = "runtime_thread", not(feature = "runtime_tokio")))]
+pub type JoinHandle<T> = std::thread::JoinHandle<T>;
+
+#[cfg(all(feature = "runtime_thread", not(feature = "runtime_tokio")))]
 pub fn spawn<F>(f: F) -> JoinHandle<()>
 where
     F: FnOnce() + Send + 'static,
@@ -27,10 +34,63 @@ where
     std::thread::spawn(f)
 }
 
-#[cfg(feature = "runtime_thread")]
+#[cfg(all(feature = "runtime_thread", not(feature = "runtime_tokio")))]
 pub fn spawn_stack_size<F>(f: F, _stack_size: usize) -> JoinHandle<()>
 where
     F: FnOnce() + Send + 'static,
 {
     std::thread::spawn(f)
 }
+
+/// the runtime of the pipeline loops and of the AsyncLogAppender tasks.
+/// it is owned by fast_log rather than the application, which can shut down before the logger is flushed
+#[cfg(feature = "runtime_tokio")]
+pub static RUNTIME: once_cell::sync::Lazy<tokio::runtime::Runtime> =
+    once_cell::sync::Lazy::new(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("fast_log")
+            .enable_all()
+            .build()
+            .expect("[fast_log] build tokio runtime fail")
+    });
+
+/// joins a loop spawned by spawn, like std::thread::JoinHandle
+#[cfg(feature = "runtime_tokio")]
+#[derive(Debug)]
+pub struct JoinHandle<T> {
+    result: Receiver<std::thread::Result<T>>,
+}
+
+#[cfg(feature = "runtime_tokio")]
+impl<T> JoinHandle<T> {
+    /// wait for the task to finish, Err if it panicked.
+    /// blocks the current thread, it can be called outside of a runtime
+    pub fn join(self) -> std::thread::Result<T> {
+        self.result
+            .recv()
+            .unwrap_or_else(|_| Err(Box::new("[fast_log] task cancelled")))
+    }
+}
+
+/// run f on the blocking pool of RUNTIME. the loops block on channels and appenders,
+/// so they are not async tasks and never stall the runtime workers
+#[cfg(feature = "runtime_tokio")]
+pub fn spawn<F>(f: F) -> JoinHandle<()>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let (send, result) = chan(Some(1));
+    RUNTIME.spawn_blocking(move || {
+        let _ = send.send(std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)));
+    });
+    JoinHandle { result }
+}
+
+#[cfg(feature = "runtime_tokio")]
+pub fn spawn_stack_size<F>(f: F, _stack_size: usize) -> JoinHandle<()>
+where
+    F: FnOnce() + Send + 'static,
+{
+    spawn(f)
+}
@@ -0,0 +1,249 @@
+use crate::appender::{Command, FastLogRecord, FieldValue};
+use crate::config::Config;
+use log::Level;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// what a RateLimit bucket is keyed by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitKey {
+    /// one bucket per record target
+    Target,
+    /// one bucket per call site(file:line)
+    CallSite,
+}
+
+/// token bucket limit: at most `burst` records at once, refilled at `burst` records per `period`.
+/// suppressed records are reported by an "N records suppressed" record when the bucket lets records through again,
+/// or after IDLE without records of the bucket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub key: LimitKey,
+    pub burst: u64,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub fn per_target(burst: u64, period: Duration) -> Self {
+        Self {
+            key: LimitKey::Target,
+            burst,
+            period,
+        }
+    }
+
+    pub fn per_call_site(burst: u64, period: Duration) -> Self {
+        Self {
+            key: LimitKey::CallSite,
+            burst,
+            period,
+        }
+    }
+}
+
+/// the repeats and suppressed records are reported after this long without them,
+/// so the summary is not held back until the next record
+pub const IDLE: Duration = Duration::from_secs(1);
+
+struct Bucket {
+    tokens: f64,
+    last: SystemTime,
+    suppressed: u64,
+    level: Level,
+    target: Cow<'static, str>,
+}
+
+/// the dedup and rate limit stage, runs on the format thread before records are formatted.
+/// times are taken from FastLogRecord.now
+pub(crate) struct Suppressor {
+    dedup: bool,
+    limit: Option<RateLimit>,
+    /// the last record passed by dedup, and how many times it was repeated since
+    last_level: Option<Level>,
+    last_target: Cow<'static, str>,
+    last_args: String,
+    /// the text of print records
+    last_formated: String,
+    last_fields: Vec<(String, FieldValue)>,
+    last_context: Option<Arc<Vec<(String, FieldValue)>>>,
+    repeated: u64,
+    /// the time of the last repeat
+    last_repeat: SystemTime,
+    buckets: HashMap<String, Bucket>,
+}
+
+impl Suppressor {
+    /// None if Config has neither dedup nor rate_limit
+    pub fn new(cfg: &Config) -> Option<Self> {
+        if !cfg.dedup && cfg.rate_limit.is_none() {
+            return None;
+        }
+        Some(Self {
+            dedup: cfg.dedup,
+            limit: cfg.rate_limit,
+            last_level: None,
+            last_target: Cow::Borrowed(""),
+            last_args: String::new(),
+            last_formated: String::new(),
+            last_fields: vec![],
+            last_context: None,
+            repeated: 0,
+            last_repeat: SystemTime::UNIX_EPOCH,
+            buckets: HashMap::new(),
+        })
+    }
+
+    pub fn process(&mut self, remain: Vec<FastLogRecord>) -> Vec<FastLogRecord> {
+        let mut out = Vec::with_capacity(remain.len());
+        for x in remain {
+            match x.command {
+                Command::CommandRecord => {
+                    if self.dedup && self.is_repeated(&x) {
+                        self.repeated += 1;
+                        self.last_repeat = self.last_repeat.max(x.now);
+                        continue;
+                    }
+                    self.report_repeated(&mut out, x.now);
+                    if !self.take_token(&mut out, &x) {
+                        continue;
+                    }
+                    if self.dedup {
+                        self.last_level = Some(x.level);
+                        self.last_target = x.target.clone();
+                        self.last_args.clear();
+                        self.last_args.push_str(&x.args);
+                        self.last_formated.clear();
+                        self.last_formated.push_str(&x.formated);
+                        self.last_fields.clone_from(&x.fields);
+                        self.last_context.clone_from(&x.context);
+                    }
+                    out.push(x);
+                }
+                Command::CommandFlush(_) => {
+                    self.report_repeated(&mut out, x.now);
+                    out.push(x);
+                }
+                Command::CommandExit => {
+                    self.report_repeated(&mut out, x.now);
+                    for bucket in self.buckets.values_mut() {
+                        bucket.report(&mut out, x.now);
+                    }
+                    out.push(x);
+                }
+            }
+        }
+        out
+    }
+
+    /// how long the format thread may wait before a summary is due, None is forever
+    pub fn timeout(&self) -> Option<Duration> {
+        let now = SystemTime::now();
+        let mut pending = self
+            .buckets
+            .values()
+            .filter(|x| x.suppressed > 0)
+            .map(|x| x.last)
+            .collect::<Vec<_>>();
+        if self.repeated > 0 {
+            pending.push(self.last_repeat);
+        }
+        pending
+            .into_iter()
+            .map(|last| IDLE.saturating_sub(now.duration_since(last).unwrap_or_default()))
+            .min()
+    }
+
+    /// report the repeats and suppressed records that have been idle for IDLE
+    pub fn idle(&mut self, out: &mut Vec<FastLogRecord>) {
+        let now = SystemTime::now();
+        let is_idle = |last: SystemTime| now.duration_since(last).unwrap_or_default() >= IDLE;
+        if self.repeated > 0 && is_idle(self.last_repeat) {
+            self.report_repeated(out, now);
+        }
+        for bucket in self.buckets.values_mut() {
+            if bucket.suppressed > 0 && is_idle(bucket.last) {
+                bucket.report(out, now);
+            }
+        }
+    }
+
+    fn is_repeated(&self, x: &FastLogRecord) -> bool {
+        self.last_level == Some(x.level)
+            && self.last_target == x.target
+            && self.last_args == x.args
+            && self.last_formated == x.formated
+            && self.last_fields == x.fields
+            && self.last_context == x.context
+    }
+
+    /// "last message repeated N times" when the repeats end
+    fn report_repeated(&mut self, out: &mut Vec<FastLogRecord>, now: SystemTime) {
+        if self.repeated == 0 {
+            return;
+        }
+        if let Some(level) = self.last_level {
+            out.push(notice(
+                level,
+                self.last_target.clone(),
+                format!("[fast_log] last message repeated {} times", self.repeated),
+                now,
+            ));
+        }
+        self.repeated = 0;
+        //the next identical record is logged again
+        self.last_level = None;
+    }
+
+    /// false if the record is suppressed by the rate limit
+    fn take_token(&mut self, out: &mut Vec<FastLogRecord>, x: &FastLogRecord) -> bool {
+        let limit = match &self.limit {
+            Some(limit) => *limit,
+            None => return true,
+        };
+        let key = match limit.key {
+            LimitKey::Target => x.target.to_string(),
+            LimitKey::CallSite => format!("{}:{}", x.file, x.line.unwrap_or_default()),
+        };
+        let bucket = self.buckets.entry(key).or_insert_with(|| Bucket {
+            tokens: limit.burst as f64,
+            last: x.now,
+            suppressed: 0,
+            level: x.level,
+            target: x.target.clone(),
+        });
+        let elapsed = x.now.duration_since(bucket.last).unwrap_or_default();
+        bucket.last = bucket.last.max(x.now);
+        let rate = limit.burst as f64 / limit.period.as_secs_f64().max(f64::EPSILON);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(limit.burst as f64);
+        if bucket.tokens < 1.0 {
+            bucket.suppressed += 1;
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        bucket.report(out, x.now);
+        true
+    }
+}
+
+impl Bucket {
+    /// "N records suppressed" if records were suppressed since the last report
+    fn report(&mut self, out: &mut Vec<FastLogRecord>, now: SystemTime) {
+        if self.suppressed > 0 {
+            out.push(notice(
+                self.level,
+                self.target.clone(),
+                format!("[fast_log] {} records suppressed", self.suppressed),
+                now,
+            ));
+            self.suppressed = 0;
+        }
+    }
+}
+
+fn notice(level: Level, target: Cow<'static, str>, args: String, now: SystemTime) -> FastLogRecord {
+    let mut record = FastLogRecord::notice(level, target, args);
+    record.now = now;
+    record
+}
@@ -0,0 +1,184 @@
+use crate::appender::FieldValue;
+use crate::fast_log::{Logger, LOGGER};
+use log::kv::{self, Key, Source, Value, VisitSource};
+use std::fmt::Debug;
+use std::ops::Deref;
+use tracing_core::field::{Field, Visit};
+use tracing_core::span::{Attributes, Id, Record};
+use tracing_core::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// a tracing-subscriber Layer that logs tracing events through a fast_log Logger(feature tracing).
+/// the event message is the record args, event fields,span fields and the span names("span")
+/// become structured fields. for example:
+// use tracing_subscriber::layer::SubscriberExt;
+// fast_log::init(Config::new().console()).unwrap();
+// let subscriber = tracing_subscriber::registry().with(FastLogLayer::new());
+// tracing::subscriber::set_global_default(subscriber).unwrap();
+pub struct FastLogLayer {
+    logger: &'static Logger,
+}
+
+impl Default for FastLogLayer {
+    fn default() -> Self {
+        Self {
+            logger: LOGGER.deref(),
+        }
+    }
+}
+
+impl FastLogLayer {
+    /// log through the global LOGGER
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// log through a standalone Logger
+    pub fn with_logger(logger: &'static Logger) -> Self {
+        Self { logger }
+    }
+}
+
+/// the fields of a span, kept in the span extensions
+struct SpanFields(Vec<(String, FieldValue)>);
+
+impl<S> Layer<S> for FastLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldCollector::default();
+            attrs.record(&mut visitor);
+            span.extensions_mut().insert(SpanFields(visitor.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = FieldCollector::default();
+            values.record(&mut visitor);
+            if let Some(fields) = span.extensions_mut().get_mut::<SpanFields>() {
+                fields.0.append(&mut visitor.fields);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        let level = to_log_level(meta.level());
+        if level > self.logger.get_level() {
+            return;
+        }
+        let mut fields = vec![];
+        if let Some(scope) = ctx.event_scope(event) {
+            let mut names = String::new();
+            for span in scope.from_root() {
+                if !names.is_empty() {
+                    names.push(':');
+                }
+                names.push_str(span.name());
+                if let Some(span_fields) = span.extensions().get::<SpanFields>() {
+                    fields.extend(span_fields.0.iter().cloned());
+                }
+            }
+            fields.insert(0, ("span".to_string(), FieldValue::Str(names)));
+        }
+        let mut visitor = FieldCollector::default();
+        event.record(&mut visitor);
+        fields.append(&mut visitor.fields);
+        let message = visitor.message.unwrap_or_default();
+        log::Log::log(
+            self.logger,
+            &log::Record::builder()
+                .level(level)
+                .target(meta.target())
+                .module_path_static(meta.module_path())
+                .file_static(meta.file())
+                .line(meta.line())
+                .args(format_args!("{}", message))
+                .key_values(&Fields(&fields))
+                .build(),
+        );
+    }
+}
+
+fn to_log_level(level: &Level) -> log::Level {
+    match *level {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        Level::TRACE => log::Level::Trace,
+    }
+}
+
+/// collect tracing fields, the "message" field is kept apart
+#[derive(Default)]
+struct FieldCollector {
+    message: Option<String>,
+    fields: Vec<(String, FieldValue)>,
+}
+
+impl Visit for FieldCollector {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields
+            .push((field.name().to_string(), FieldValue::F64(value)));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields
+            .push((field.name().to_string(), FieldValue::I64(value)));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields
+            .push((field.name().to_string(), FieldValue::U64(value)));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields
+            .push((field.name().to_string(), FieldValue::Bool(value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.fields
+                .push((field.name().to_string(), FieldValue::Str(value.to_string())));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{:?}", value));
+        } else {
+            self.fields.push((
+                field.name().to_string(),
+                FieldValue::Str(format!("{:?}", value)),
+            ));
+        }
+    }
+}
+
+/// fields as log key-values, Logger::log collects them back into FastLogRecord fields
+struct Fields<'a>(&'a [(String, FieldValue)]);
+
+impl Source for Fields<'_> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn VisitSource<'kvs>) -> Result<(), kv::Error> {
+        for (k, v) in self.0 {
+            let value = match v {
+                FieldValue::Str(v) => Value::from(v.as_str()),
+                FieldValue::I64(v) => Value::from(*v),
+                FieldValue::U64(v) => Value::from(*v),
+                FieldValue::F64(v) => Value::from(*v),
+                FieldValue::Bool(v) => Value::from(*v),
+            };
+            visitor.visit_pair(Key::from_str(k), value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::{Config, FastLogFormatJson};
+    use log::LevelFilter;
+
+    #[test]
+    fn test_appender_level_and_format() {
+        let text = CaptureAppender::new();
+        let json = CaptureAppender::new();
+        fast_log::init(
+            Config::new()
+                .level(LevelFilter::Debug)
+                .custom(text.clone())
+                .appender_level(LevelFilter::Info)
+                .custom(json.clone())
+                .appender_format(FastLogFormatJson::new()),
+        )
+        .unwrap();
+        log::debug!("debug");
+        log::info!("info");
+        log::logger().flush();
+        let text = text.lines();
+        assert_eq!(text.len(), 1);
+        assert!(text[0].ends_with("[INFO] info\n"));
+        let json = json.lines();
+        assert_eq!(json.len(), 2);
+        assert!(json[0].starts_with("{\"args\":\"debug\""));
+        assert!(json[1].starts_with("{\"args\":\"info\""));
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(all(test, feature = "runtime_tokio"))]
+mod test {
+    use fast_log::appender::{AsyncLogAppender, Command, FastLogRecord, LogAppender};
+    use fast_log::{Config, Logger};
+    use log::{LevelFilter, Log};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    struct SlowAppender {
+        lines: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl AsyncLogAppender for SlowAppender {
+        async fn do_logs(&self, records: &[FastLogRecord]) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            for x in records {
+                if x.command == Command::CommandRecord {
+                    self.lines.lock().unwrap().push(x.args.clone());
+                }
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_appender() {
+        log::set_max_level(LevelFilter::Trace);
+        let lines = Arc::new(Mutex::new(vec![]));
+        let logger = Logger::new(Config::new().custom_async(SlowAppender {
+            lines: lines.clone(),
+        }))
+        .unwrap();
+        log::info!(logger: logger, "first");
+        //flush waits for the task
+        tokio::task::block_in_place(|| logger.flush());
+        assert_eq!(*lines.lock().unwrap(), vec!["first".to_string()]);
+        log::info!(logger: logger, "second");
+        //shutdown waits for the task too
+        let summary = tokio::task::block_in_place(|| logger.shutdown()).unwrap();
+        assert_eq!(summary.written, vec![2]);
+        assert_eq!(lines.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_async_appender_current_thread() {
+        log::set_max_level(LevelFilter::Trace);
+        let lines = Arc::new(Mutex::new(vec![]));
+        let logger = Logger::new(Config::new().custom_async(SlowAppender {
+            lines: lines.clone(),
+        }))
+        .unwrap();
+        log::info!(logger: logger, "first");
+        //the task runs on fast_log's runtime, blocking the only worker of this one is fine
+        logger.flush();
+        assert_eq!(*lines.lock().unwrap(), vec!["first".to_string()]);
+        let summary = logger.shutdown().unwrap();
+        assert_eq!(summary.written, vec![1]);
+    }
+
+    struct ThreadAppender {
+        threads: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl LogAppender for ThreadAppender {
+        fn do_logs(&self, _records: &[FastLogRecord]) {
+            let name = std::thread::current().name().map(|v| v.to_string());
+            self.threads.lock().unwrap().push(name);
+        }
+    }
+
+    #[test]
+    fn test_runtime_tokio_outside_runtime() {
+        log::set_max_level(LevelFilter::Trace);
+        let threads = Arc::new(Mutex::new(vec![]));
+        let lines = Arc::new(Mutex::new(vec![]));
+        //no runtime here, the loops and the async appender run on fast_log's runtime
+        let logger = Logger::new(
+            Config::new()
+                .custom(ThreadAppender {
+                    threads: threads.clone(),
+                })
+                .custom_async(SlowAppender {
+                    lines: lines.clone(),
+                }),
+        )
+        .unwrap();
+        log::info!(logger: logger, "first");
+        logger.flush();
+        assert_eq!(*lines.lock().unwrap(), vec!["first".to_string()]);
+        let summary = logger.shutdown().unwrap();
+        assert_eq!(summary.written, vec![1, 1]);
+        let threads = threads.lock().unwrap();
+        assert!(!threads.is_empty());
+        for name in threads.iter() {
+            assert_eq!(name.as_deref(), Some("fast_log"));
+        }
+    }
+}
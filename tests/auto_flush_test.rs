@@ -0,0 +1,32 @@
+#[cfg(test)]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::Config;
+    use log::Level;
+    use std::time::Duration;
+
+    #[test]
+    fn test_flush_level() {
+        let (logger, capture) =
+            CaptureAppender::logger(Config::new().flush_level(Level::Error)).unwrap();
+        log::info!(logger: logger, "info");
+        log::error!(logger: logger, "error");
+        assert!(capture.wait_for_flush(1, Duration::from_secs(5)));
+        //the flush comes after the error
+        assert_eq!(capture.messages(), vec!["info", "error"]);
+        assert_eq!(capture.flushes(), 1);
+    }
+
+    #[test]
+    fn test_flush_interval() {
+        let (logger, capture) =
+            CaptureAppender::logger(Config::new().flush_interval(Duration::from_millis(50)))
+                .unwrap();
+        log::info!(logger: logger, "info");
+        assert!(capture.wait_for_flush(1, Duration::from_secs(5)));
+        //idle appenders are not flushed again
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(capture.messages(), vec!["info"]);
+        assert_eq!(capture.flushes(), 1);
+    }
+}
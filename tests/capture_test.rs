@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::{Config, Logger};
+    use log::{Level, LevelFilter, Log};
+    use std::time::Duration;
+
+    #[test]
+    fn test_capture() {
+        log::set_max_level(LevelFilter::Trace);
+        let capture = CaptureAppender::new();
+        let logger = Logger::new(Config::new().custom(capture.clone())).unwrap();
+        log::info!(logger: logger, "Commencing yak shaving");
+        log::warn!(logger: logger, target: "yak::razor", "razor is dull");
+        assert!(capture.wait_for(2, Duration::from_secs(5)));
+        assert_eq!(capture.len(), 2);
+        capture.assert_contains(Level::Info, "capture_test", "yak shaving");
+        capture.assert_contains(Level::Warn, "yak", "dull");
+        assert!(!capture.contains(Level::Error, "", "yak"));
+        let lines = capture.lines();
+        assert!(lines[0].ends_with("Commencing yak shaving\n"));
+        let records = capture.records();
+        assert_eq!(records[1].target, "yak::razor");
+        assert_eq!(
+            capture.messages(),
+            vec!["Commencing yak shaving", "razor is dull"]
+        );
+        //flush commands are counted, not captured
+        logger.flush();
+        assert!(capture.wait_for_flush(1, Duration::from_secs(5)));
+        assert_eq!(capture.flushes(), 1);
+        assert_eq!(capture.len(), 2);
+        capture.clear();
+        assert!(capture.is_empty());
+        assert!(!capture.wait_for(1, Duration::from_millis(10)));
+    }
+
+    #[test]
+    #[should_panic(expected = "no record matches level=ERROR")]
+    fn test_assert_contains() {
+        log::set_max_level(LevelFilter::Trace);
+        let capture = CaptureAppender::new();
+        let logger = Logger::new(Config::new().custom(capture.clone())).unwrap();
+        log::info!(logger: logger, "Commencing yak shaving");
+        assert!(capture.wait_for(1, Duration::from_secs(5)));
+        capture.assert_contains(Level::Error, "", "yak");
+    }
+}
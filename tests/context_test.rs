@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::{context, Config, FastLogFormat};
+    use log::Log;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_push() {
+        let (logger, capture) =
+            CaptureAppender::logger(Config::new().format(FastLogFormat::new())).unwrap();
+        {
+            let _request = context::push("request_id", 42);
+            {
+                let _user = context::push("user", "yak");
+                log::info!(logger: logger, "inner");
+            }
+            log::info!(logger: logger, "outer");
+        }
+        log::info!(logger: logger, "none");
+        assert!(context::current().is_none());
+        logger.flush();
+        let lines = capture.lines();
+        assert!(lines[0].ends_with("[INFO] inner request_id=42 user=yak\n"));
+        assert!(lines[1].ends_with("[INFO] outer request_id=42\n"));
+        assert!(lines[2].ends_with("[INFO] none\n"));
+    }
+
+    #[tokio::test]
+    async fn test_scope() {
+        let (logger, capture) =
+            CaptureAppender::logger(Config::new().format(FastLogFormat::new())).unwrap();
+        let logger = Arc::new(logger);
+        let task = |id: u64| {
+            let logger = logger.clone();
+            context::scope("request_id", id, async move {
+                log::info!(logger: logger, "begin");
+                tokio::task::yield_now().await;
+                log::info!(logger: logger, "end");
+            })
+        };
+        //interleaved tasks on one thread keep their own context
+        tokio::join!(task(1), task(2));
+        assert!(context::current().is_none());
+        logger.flush();
+        let lines = capture.lines();
+        assert_eq!(lines.len(), 4);
+        for id in [1, 2] {
+            let suffix = format!("request_id={}\n", id);
+            assert_eq!(lines.iter().filter(|x| x.ends_with(&suffix)).count(), 2);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod test {
+    use fast_log::appender::{ErrorAction, FastLogRecord, LogAppender};
+    use fast_log::error::LogError;
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::{Config, Logger};
+    use log::{LevelFilter, Log};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// fails the first `fails` calls
+    struct FailAppender {
+        fails: AtomicUsize,
+        capture: CaptureAppender,
+    }
+
+    impl LogAppender for FailAppender {
+        fn do_logs(&self, records: &[FastLogRecord]) {
+            let _ = self.try_do_logs(records);
+        }
+
+        fn try_do_logs(&self, records: &[FastLogRecord]) -> Result<(), LogError> {
+            if self.fails.load(Ordering::SeqCst) > 0 {
+                self.fails.fetch_sub(1, Ordering::SeqCst);
+                return Err(LogError::from("No space left on device"));
+            }
+            self.capture.do_logs(records);
+            Ok(())
+        }
+    }
+
+    fn fail_config(fails: usize) -> (Config, CaptureAppender) {
+        let capture = CaptureAppender::new();
+        let config = Config::new().custom(FailAppender {
+            fails: AtomicUsize::new(fails),
+            capture: capture.clone(),
+        });
+        (config, capture)
+    }
+
+    #[test]
+    fn test_fallback() {
+        log::set_max_level(LevelFilter::Trace);
+        let (config, capture) = fail_config(1);
+        let fallback = CaptureAppender::new();
+        let logger = Logger::new(config.fallback(fallback.clone())).unwrap();
+        log::info!(logger: logger, "lost");
+        logger.flush();
+        log::info!(logger: logger, "written");
+        logger.flush();
+        assert_eq!(fallback.messages(), vec!["lost".to_string()]);
+        assert_eq!(capture.messages(), vec!["written".to_string()]);
+        assert_eq!(logger.metrics().unwrap().appenders[0].errors, 1);
+        let summary = logger.shutdown().unwrap();
+        assert_eq!(summary.written, vec![1]);
+        assert_eq!(summary.errors.len(), 1);
+        assert_eq!(
+            summary.errors[0].to_string(),
+            "Rbatis Error: [fast_log] appender 0 write fail: No space left on device"
+        );
+    }
+
+    #[test]
+    fn test_fallback_fail() {
+        log::set_max_level(LevelFilter::Trace);
+        let (config, capture) = fail_config(1);
+        let fallback = FailAppender {
+            fails: AtomicUsize::new(1),
+            capture: CaptureAppender::new(),
+        };
+        let logger = Logger::new(config.fallback(fallback)).unwrap();
+        log::info!(logger: logger, "lost");
+        logger.flush();
+        assert!(capture.is_empty());
+        assert_eq!(logger.metrics().unwrap().appenders[0].errors, 2);
+        let summary = logger.shutdown().unwrap();
+        assert_eq!(summary.errors.len(), 2);
+        assert_eq!(
+            summary.errors[1].to_string(),
+            "Rbatis Error: [fast_log] appender 0 fallback fail: No space left on device"
+        );
+    }
+
+    #[test]
+    fn test_retry() {
+        log::set_max_level(LevelFilter::Trace);
+        let (config, capture) = fail_config(2);
+        let logger = Logger::new(config.error_handler(|e| {
+            if e.retries < 3 {
+                ErrorAction::Retry
+            } else {
+                ErrorAction::Ignore
+            }
+        }))
+        .unwrap();
+        log::info!(logger: logger, "retried");
+        logger.flush();
+        assert_eq!(capture.messages(), vec!["retried".to_string()]);
+        assert_eq!(logger.metrics().unwrap().appenders[0].errors, 2);
+    }
+
+    #[test]
+    fn test_disable() {
+        log::set_max_level(LevelFilter::Trace);
+        let (config, capture) = fail_config(1);
+        let logger = Logger::new(config.error_handler(|_| ErrorAction::Disable)).unwrap();
+        log::info!(logger: logger, "lost");
+        logger.flush();
+        log::info!(logger: logger, "skipped");
+        logger.flush();
+        assert!(capture.is_empty());
+        //a disabled appender still gets flush and exit
+        assert_eq!(capture.flushes(), 2);
+        let summary = logger.shutdown().unwrap();
+        assert_eq!(summary.written, vec![0]);
+    }
+}
@@ -0,0 +1,242 @@
+#[cfg(test)]
+mod test {
+    use fast_log::appender::{
+        current_pid, current_thread, Command, FastLogRecord, FieldValue, RecordFormat,
+    };
+    use fast_log::{
+        Facility, FastLogFormat, FastLogFormatJson, JsonField, JsonTime, LogfmtFormat,
+        PatternFormat, SyslogFormat, TimeType,
+    };
+    use log::Level;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn record(args: &str, fields: Vec<(String, FieldValue)>) -> FastLogRecord {
+        FastLogRecord {
+            command: Command::CommandRecord,
+            level: Level::Info,
+            target: "format_test".into(),
+            args: args.to_string(),
+            module_path: "format_test".into(),
+            file: "tests/format_test.rs".into(),
+            line: Some(1),
+            now: SystemTime::now(),
+            thread_id: 0,
+            thread_name: None,
+            pid: 0,
+            formated: String::new(),
+            fields,
+            context: None,
+        }
+    }
+
+    #[test]
+    fn test_format_fields() {
+        let mut r = record(
+            "done",
+            vec![
+                ("request_id".to_string(), FieldValue::Str("abc".to_string())),
+                ("latency".to_string(), FieldValue::U64(12)),
+            ],
+        );
+        FastLogFormat::new().do_format(&mut r);
+        assert!(r
+            .formated
+            .ends_with("[INFO] done request_id=abc latency=12\n"));
+    }
+
+    #[test]
+    fn test_format_json_fields() {
+        let mut r = record(
+            "done",
+            vec![
+                (
+                    "request_id".to_string(),
+                    FieldValue::Str("a\"b".to_string()),
+                ),
+                ("latency".to_string(), FieldValue::F64(1.5)),
+                ("ok".to_string(), FieldValue::Bool(true)),
+            ],
+        );
+        FastLogFormatJson::new().do_format(&mut r);
+        assert!(r
+            .formated
+            .ends_with(",\"request_id\":\"a\\\"b\",\"latency\":1.5,\"ok\":true}\n"));
+    }
+
+    #[test]
+    fn test_format_thread_pid() {
+        let mut r = record("done", vec![]);
+        r.thread_id = 7;
+        r.thread_name = Some("worker".into());
+        r.pid = 42;
+        FastLogFormat::new()
+            .set_display_thread(true)
+            .set_display_pid(true)
+            .do_format(&mut r);
+        assert!(r.formated.ends_with("[INFO] [42] [worker:7] done\n"));
+        r.formated.clear();
+        FastLogFormatJson::new()
+            .set_display_thread(true)
+            .set_display_pid(true)
+            .do_format(&mut r);
+        assert!(r
+            .formated
+            .ends_with(",\"pid\":42,\"thread_id\":7,\"thread_name\":\"worker\"}\n"));
+    }
+
+    #[test]
+    fn test_pattern_format() {
+        let mut r = record("done", vec![("latency".to_string(), FieldValue::U64(12))]);
+        r.now = UNIX_EPOCH + Duration::from_millis(1_660_902_827_798);
+        r.thread_id = 7;
+        r.thread_name = Some("worker".into());
+        PatternFormat::new("%d{%Y-%m-%d %H:%M:%S%.3f%:z} %-5l|%6L|%t [%f:%L] %T %m %F%n")
+            .unwrap()
+            .set_time_type(TimeType::Utc)
+            .do_format(&mut r);
+        assert_eq!(
+            r.formated,
+            "2022-08-19 09:53:47.798+00:00 INFO |     1|format_test [tests/format_test.rs:1] worker:7 done latency=12\n"
+        );
+    }
+
+    #[test]
+    fn test_pattern_format_error() {
+        assert!(PatternFormat::new("%q").is_err());
+        assert!(PatternFormat::new("%d{%H").is_err());
+        assert!(PatternFormat::new("%d{%Q}").is_err());
+        assert!(PatternFormat::new("100%%%n").is_ok());
+    }
+
+    #[test]
+    fn test_format_json_escape() {
+        let mut r = record("line1\nline2\t\"C:\\yak\"\u{1}", vec![]);
+        FastLogFormatJson::new().do_format(&mut r);
+        assert!(r
+            .formated
+            .starts_with("{\"args\":\"line1\\nline2\\t\\\"C:\\\\yak\\\"\\u0001\",\"date\":\""));
+        assert!(r.formated.ends_with(
+            ",\"level\":\"INFO\",\"line\":1,\"target\":\"format_test\",\"module_path\":\"format_test\"}\n"
+        ));
+        //one line
+        assert_eq!(r.formated.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_format_json_options() {
+        let mut r = record("done", vec![("latency".to_string(), FieldValue::U64(12))]);
+        r.now = UNIX_EPOCH + Duration::from_millis(1_660_902_827_798);
+        FastLogFormatJson::new()
+            .set_fields(&[JsonField::Date, JsonField::Level, JsonField::Args])
+            .rename(JsonField::Args, "message")
+            .rename(JsonField::Date, "@timestamp")
+            .static_field("service", "yak-shaver")
+            .static_field("replica", 2)
+            .set_time_type(TimeType::Utc)
+            .set_json_time(JsonTime::Rfc3339)
+            .do_format(&mut r);
+        assert_eq!(
+            r.formated,
+            "{\"@timestamp\":\"2022-08-19T09:53:47.798000Z\",\"level\":\"INFO\",\"message\":\"done\",\"service\":\"yak-shaver\",\"replica\":2,\"latency\":12}\n"
+        );
+        r.formated.clear();
+        FastLogFormatJson::new()
+            .set_fields(&[JsonField::Date])
+            .set_json_time(JsonTime::EpochMillis)
+            .do_format(&mut r);
+        assert_eq!(r.formated, "{\"date\":1660902827798,\"latency\":12}\n");
+        r.formated.clear();
+        FastLogFormatJson::new()
+            .set_fields(&[])
+            .static_field("env", "prod")
+            .do_format(&mut r);
+        assert_eq!(r.formated, "{\"env\":\"prod\",\"latency\":12}\n");
+    }
+
+    #[test]
+    fn test_format_logfmt() {
+        let mut r = record(
+            "Commencing \"yak\" shaving\nnow",
+            vec![
+                ("request_id".to_string(), FieldValue::Str("abc".to_string())),
+                ("user name".to_string(), FieldValue::Str("".to_string())),
+                ("latency".to_string(), FieldValue::F64(1.5)),
+            ],
+        );
+        r.now = UNIX_EPOCH + Duration::from_millis(1_660_902_827_798);
+        r.pid = 42;
+        LogfmtFormat::new()
+            .set_time_type(TimeType::Utc)
+            .set_display_pid(true)
+            .do_format(&mut r);
+        assert_eq!(
+            r.formated,
+            "ts=2022-08-19T09:53:47.798000Z level=info target=format_test msg=\"Commencing \\\"yak\\\" shaving\\nnow\" file=tests/format_test.rs line=1 pid=42 request_id=abc user_name=\"\" latency=1.5\n"
+        );
+    }
+
+    #[test]
+    fn test_format_syslog_rfc5424() {
+        let mut r = record(
+            "Commencing yak shaving",
+            vec![
+                (
+                    "request_id".to_string(),
+                    FieldValue::Str("a\"b]".to_string()),
+                ),
+                ("latency".to_string(), FieldValue::U64(12)),
+            ],
+        );
+        r.level = Level::Warn;
+        r.now = UNIX_EPOCH + Duration::from_millis(1_660_902_827_798);
+        r.pid = 42;
+        let format = SyslogFormat::rfc5424()
+            .set_facility(Facility::Local0)
+            .set_hostname("yak-host")
+            .set_app_name("yak shaver")
+            .set_msgid("SHAVE")
+            .set_time_type(TimeType::Utc);
+        format.do_format(&mut r);
+        assert_eq!(
+            r.formated,
+            "<132>1 2022-08-19T09:53:47.798000Z yak-host yak_shaver 42 SHAVE [fields@32473 request_id=\"a\\\"b\\]\" latency=\"12\"] Commencing yak shaving\n"
+        );
+        let mut r = record("line1\nline2", vec![]);
+        r.now = UNIX_EPOCH;
+        r.pid = 42;
+        format.set_octet_counting(true).do_format(&mut r);
+        let msg = "<134>1 1970-01-01T00:00:00.000000Z yak-host yak_shaver 42 SHAVE - line1\nline2";
+        assert_eq!(r.formated, format!("{} {}", msg.len(), msg));
+    }
+
+    #[test]
+    fn test_format_syslog_rfc3164() {
+        let mut r = record(
+            "Commencing yak shaving\nnow",
+            vec![("latency".to_string(), FieldValue::U64(12))],
+        );
+        r.level = Level::Error;
+        r.now = UNIX_EPOCH + Duration::from_millis(1_659_952_427_000);
+        SyslogFormat::rfc3164()
+            .set_facility(Facility::Daemon)
+            .set_hostname("yak-host")
+            .set_app_name("yak-shaver")
+            .set_procid("7")
+            .set_time_type(TimeType::Utc)
+            .do_format(&mut r);
+        assert_eq!(
+            r.formated,
+            "<27>Aug  8 09:53:47 yak-host yak-shaver[7]: Commencing yak shaving#012now latency=12\n"
+        );
+    }
+
+    #[test]
+    fn test_current_thread_and_pid() {
+        let (id, _) = current_thread();
+        assert_ne!(id, 0);
+        assert_eq!(current_thread().0, id);
+        let other = std::thread::spawn(|| current_thread().0).join().unwrap();
+        assert_ne!(other, id);
+        assert_eq!(current_pid(), std::process::id());
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod test {
+    use fast_log::appender::{FastLogRecord, LogAppender};
+    use fast_log::config::OverflowPolicy;
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::{Config, FastLogFormatJson, Logger};
+    use log::LevelFilter;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::sync::Mutex;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_workers_keep_order() {
+        let capture = CaptureAppender::new();
+        fast_log::init(
+            Config::new()
+                .format(FastLogFormatJson::new())
+                .format_workers(4)
+                .custom(capture.clone()),
+        )
+        .unwrap();
+        for i in 0..10000 {
+            log::info!("{}", i);
+        }
+        log::logger().flush();
+        let lines = capture.lines();
+        assert_eq!(lines.len(), 10000);
+        for (i, line) in lines.iter().enumerate() {
+            assert!(line.starts_with(&format!("{{\"args\":\"{}\"", i)));
+        }
+        let summary = fast_log::shutdown().unwrap();
+        assert_eq!(summary.written, vec![10000]);
+    }
+
+    struct GateAppender {
+        gate: Mutex<Receiver<()>>,
+    }
+
+    impl LogAppender for GateAppender {
+        fn do_logs(&self, _records: &[FastLogRecord]) {
+            let _ = self.gate.lock().unwrap().recv();
+        }
+    }
+
+    #[test]
+    fn test_format_workers_backpressure() {
+        log::set_max_level(LevelFilter::Trace);
+        let (open, gate) = channel::<()>();
+        let logger = Logger::new(
+            Config::new()
+                .format_workers(4)
+                .custom(GateAppender {
+                    gate: Mutex::new(gate),
+                })
+                .chan_len(Some(10))
+                .overflow(OverflowPolicy::DropNewest),
+        )
+        .unwrap();
+        //dropped before the logger if an assert fails, so shutdown is not blocked
+        let open = open;
+        for _ in 0..200 {
+            for _ in 0..10 {
+                log::info!(logger: logger, "flood");
+            }
+            sleep(Duration::from_millis(1));
+        }
+        //the workers hold at most a few batches while the appender is stuck
+        let metrics = logger.metrics().unwrap();
+        assert!(metrics.records < 500, "accepted {}", metrics.records);
+        assert!(metrics.dropped > 1500);
+        drop(open);
+        logger.shutdown();
+    }
+}
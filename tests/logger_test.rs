@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::{Config, Logger};
+    use log::{LevelFilter, Log};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_standalone_loggers() {
+        log::set_max_level(LevelFilter::Trace);
+        let (a, a_capture) =
+            CaptureAppender::logger(Config::new().level(LevelFilter::Trace)).unwrap();
+        let (b, b_capture) =
+            CaptureAppender::logger(Config::new().level(LevelFilter::Info)).unwrap();
+        log::info!(logger: a, "a");
+        log::debug!(logger: b, "b debug");
+        log::info!(logger: b, "b");
+        a.flush();
+        b.flush();
+        assert_eq!(a_capture.messages(), vec!["a".to_string()]);
+        assert_eq!(b_capture.messages(), vec!["b".to_string()]);
+        let summary = b.shutdown().unwrap();
+        assert_eq!(summary.written, vec![1]);
+        assert!(b.send_flush().is_err());
+    }
+
+    #[test]
+    fn test_standalone_logger_drop() {
+        log::set_max_level(LevelFilter::Trace);
+        let (logger, capture) =
+            CaptureAppender::logger(Config::new().level(LevelFilter::Trace)).unwrap();
+        for _ in 0..100 {
+            log::info!(logger: logger, "yak");
+        }
+        //drop joins the pipeline, nothing is lost
+        drop(logger);
+        assert_eq!(capture.len(), 100);
+    }
+
+    #[test]
+    fn test_install() {
+        let (logger, capture) =
+            CaptureAppender::logger(Config::new().level(LevelFilter::Info)).unwrap();
+        let logger: &'static Logger = Box::leak(Box::new(logger));
+        logger.install().unwrap();
+        log::info!("global");
+        //like LOGGER, the installed logger follows log::set_max_level
+        log::set_max_level(LevelFilter::Debug);
+        log::debug!("raised");
+        log::logger().flush();
+        assert_eq!(
+            capture.messages(),
+            vec!["global".to_string(), "raised".to_string()]
+        );
+        //still usable after install
+        assert_eq!(logger.shutdown().unwrap().written, vec![2]);
+    }
+
+    #[test]
+    fn test_thread_captured() {
+        log::set_max_level(LevelFilter::Trace);
+        let capture = CaptureAppender::new();
+        let logger = Arc::new(Logger::new(Config::new().custom(capture.clone())).unwrap());
+        let l = logger.clone();
+        std::thread::Builder::new()
+            .name("worker-1".to_string())
+            .spawn(move || log::info!(logger: l, "from worker"))
+            .unwrap()
+            .join()
+            .unwrap();
+        logger.flush();
+        let records = capture.records();
+        assert_eq!(records[0].thread_name.as_deref(), Some("worker-1"));
+        assert_eq!(records[0].pid, std::process::id());
+    }
+}
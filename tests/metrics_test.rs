@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod test {
+    use fast_log::appender::{FastLogRecord, LogAppender};
+    use fast_log::consts::LogSize;
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::plugin::file_split::KeepType;
+    use fast_log::plugin::packer::LogPacker;
+    use fast_log::{Config, Logger};
+    use log::{LevelFilter, Log};
+    use std::fs::remove_dir_all;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    struct NopAppender {}
+
+    impl LogAppender for NopAppender {
+        fn do_logs(&self, _records: &[FastLogRecord]) {}
+    }
+
+    struct PanicAppender {}
+
+    impl LogAppender for PanicAppender {
+        fn do_logs(&self, _records: &[FastLogRecord]) {
+            panic!("appender panic");
+        }
+    }
+
+    #[test]
+    fn test_panicked_appender_does_not_block_flush() {
+        let (logger, capture) =
+            CaptureAppender::logger(Config::new().custom(PanicAppender {}).chan_len(Some(10)))
+                .unwrap();
+        let (done, flushed) = channel::<()>();
+        std::thread::spawn(move || {
+            for _ in 0..100 {
+                log::info!(logger: logger, "Commencing yak shaving");
+                logger.flush();
+            }
+            let _ = done.send(());
+        });
+        assert!(flushed.recv_timeout(Duration::from_secs(10)).is_ok());
+        assert_eq!(capture.len(), 100);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let _ = remove_dir_all("target/test_metrics/");
+        log::set_max_level(LevelFilter::Trace);
+        let logger = Logger::new(Config::new().custom(NopAppender {}).file_split(
+            "target/test_metrics/",
+            LogSize::KB(1),
+            KeepType::All,
+            LogPacker {},
+        ))
+        .unwrap();
+        for _ in 0..100 {
+            log::info!(logger: logger, "Commencing yak shaving");
+        }
+        logger.flush();
+        let metrics = logger.metrics().unwrap();
+        assert_eq!(metrics.records, 100);
+        assert_eq!(metrics.dropped, 0);
+        assert_eq!(metrics.appenders.len(), 2);
+        for x in &metrics.appenders {
+            assert_eq!(x.records, 100);
+            assert!(x.bytes > 100 * "Commencing yak shaving".len() as u64);
+            assert!(x.batches > 0);
+            assert!(x.max_batch_latency >= x.avg_batch_latency);
+        }
+        assert_eq!(metrics.appenders[0].rotations, 0);
+        assert!(metrics.appenders[1].rotations > 0);
+        drop(logger);
+        let _ = remove_dir_all("target/test_metrics/");
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod test {
+    use fast_log::appender::{FastLogRecord, LogAppender};
+    use fast_log::config::OverflowPolicy;
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::{Config, Logger};
+    use log::LevelFilter;
+    use std::sync::mpsc::{channel, Receiver};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    struct GateAppender {
+        gate: Mutex<Receiver<()>>,
+        capture: CaptureAppender,
+    }
+
+    impl LogAppender for GateAppender {
+        fn do_logs(&self, records: &[FastLogRecord]) {
+            let _ = self.gate.lock().unwrap().recv();
+            self.capture.do_logs(records);
+        }
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let (open, gate) = channel::<()>();
+        let capture = CaptureAppender::new();
+        let logger = fast_log::init(
+            Config::new()
+                .custom(GateAppender {
+                    gate: Mutex::new(gate),
+                    capture: capture.clone(),
+                })
+                .chan_len(Some(10))
+                .overflow(OverflowPolicy::DropNewest),
+        )
+        .unwrap();
+        //the appender is blocked, must not block the caller
+        for _ in 0..1000 {
+            log::info!("flood");
+        }
+        assert!(logger.dropped() > 0);
+        //open the gate
+        drop(open);
+        log::logger().flush();
+        log::info!("after");
+        log::logger().flush();
+        let lines = capture.messages();
+        assert!(lines.iter().any(|x| x.ends_with("records dropped")));
+        assert_eq!(lines.last().unwrap(), "after");
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        log::set_max_level(LevelFilter::Trace);
+        let (open, gate) = channel::<()>();
+        let capture = CaptureAppender::new();
+        let logger = Logger::new(
+            Config::new()
+                .custom(GateAppender {
+                    gate: Mutex::new(gate),
+                    capture: capture.clone(),
+                })
+                .chan_len(Some(10))
+                .overflow(OverflowPolicy::DropOldest),
+        )
+        .unwrap();
+        //dropped before the logger if an assert fails, so shutdown is not blocked
+        let open = open;
+        for i in 0..100 {
+            log::info!(logger: logger, "{}", i);
+        }
+        //the flush command is queued behind the records and must survive the drops
+        let wg = logger.send_flush().unwrap();
+        for i in 100..1000 {
+            log::info!(logger: logger, "{}", i);
+        }
+        assert!(logger.dropped() > 0);
+        drop(open);
+        let (done, flushed) = channel::<()>();
+        std::thread::spawn(move || {
+            wg.wait();
+            let _ = done.send(());
+        });
+        assert!(flushed.recv_timeout(Duration::from_secs(5)).is_ok());
+        logger.shutdown();
+        let lines = capture.messages();
+        //the oldest records made room for the newest
+        assert!(lines.iter().any(|x| x == "999"));
+        let kept = lines
+            .iter()
+            .filter(|x| !x.ends_with("records dropped"))
+            .count();
+        assert!(kept < 1000);
+    }
+
+    #[test]
+    fn test_block_timeout() {
+        log::set_max_level(LevelFilter::Trace);
+        let (open, gate) = channel::<()>();
+        let capture = CaptureAppender::new();
+        let logger = Logger::new(
+            Config::new()
+                .custom(GateAppender {
+                    gate: Mutex::new(gate),
+                    capture: capture.clone(),
+                })
+                .chan_len(Some(1))
+                .overflow(OverflowPolicy::BlockTimeout(Duration::from_millis(20))),
+        )
+        .unwrap();
+        let open = open;
+        for i in 0..20 {
+            log::info!(logger: logger, "{}", i);
+        }
+        assert!(logger.dropped() > 0);
+        //the channel is full, the caller waits the timeout and gives up
+        let dropped = logger.dropped();
+        let start = Instant::now();
+        log::info!(logger: logger, "timeout");
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(20));
+        assert!(elapsed < Duration::from_secs(5));
+        assert_eq!(logger.dropped(), dropped + 1);
+        drop(open);
+        logger.shutdown();
+        assert!(!capture.messages().iter().any(|x| x == "timeout"));
+    }
+}
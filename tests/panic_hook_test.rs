@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::Config;
+    use log::Level;
+
+    #[test]
+    fn test_panic_hook() {
+        let capture = CaptureAppender::new();
+        fast_log::init(Config::new().panic_hook(true).custom(capture.clone())).unwrap();
+        let result = std::thread::Builder::new()
+            .name("yak-shaver".to_string())
+            .spawn(|| panic!("out of yaks"))
+            .unwrap()
+            .join();
+        assert!(result.is_err());
+        //the hook has flushed before the thread finished unwinding
+        let records = capture.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, Level::Error);
+        let args = &records[0].args;
+        assert!(args.starts_with("thread 'yak-shaver' panicked at tests/panic_hook_test.rs:"));
+        assert!(args.contains("out of yaks"));
+        assert!(args.contains("stack backtrace:"));
+    }
+}
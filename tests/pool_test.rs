@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::pool::{BufferPool, BUFFER_POOL};
+    use fast_log::Config;
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_buffer_pool() {
+        let pool = BufferPool::new(1, 16);
+        pool.put(String::with_capacity(1024));
+        assert!(pool.is_empty());
+        let mut buf = String::with_capacity(8);
+        buf.push_str("yak");
+        pool.put(buf);
+        pool.put(String::with_capacity(8));
+        assert_eq!(pool.len(), 1);
+        let buf = pool.get();
+        assert!(buf.is_empty());
+        assert!(buf.capacity() >= 8);
+        assert_eq!(pool.get().capacity(), 0);
+    }
+
+    #[test]
+    fn test_records_recycled() {
+        let capture = CaptureAppender::new();
+        fast_log::init(Config::new().custom(capture.clone())).unwrap();
+        for _ in 0..100 {
+            log::info!("Commencing yak shaving");
+        }
+        log::logger().flush();
+        let records = capture.records();
+        assert_eq!(records.len(), 100);
+        assert!(records.iter().all(|x| matches!(x.target, Cow::Borrowed(_))
+            && matches!(x.module_path, Cow::Borrowed(_))
+            && matches!(x.file, Cow::Borrowed(_))));
+        //the written records gave their buffers back
+        assert!(!BUFFER_POOL.is_empty());
+    }
+}
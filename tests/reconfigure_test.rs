@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod test {
+    use fast_log::consts::LogSize;
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::plugin::file_split::RollingType;
+    use fast_log::plugin::packer::LogPacker;
+    use fast_log::{Config, Logger};
+    use log::LevelFilter;
+    use std::fs::{read_to_string, remove_dir_all};
+
+    #[test]
+    fn test_reconfigure() {
+        let first = CaptureAppender::new();
+        let second = CaptureAppender::new();
+        fast_log::init(Config::new().custom(first.clone())).unwrap();
+        assert!(fast_log::init(Config::new().console()).is_err());
+        log::info!("first");
+        fast_log::reconfigure(Config::new().custom(second.clone())).unwrap();
+        //old appender must have written everything before reconfigure returns
+        assert_eq!(first.messages(), vec!["first".to_string()]);
+        log::info!("second");
+        log::logger().flush();
+        assert_eq!(first.messages(), vec!["first".to_string()]);
+        assert_eq!(second.messages(), vec!["second".to_string()]);
+        //init again after shutdown
+        let third = CaptureAppender::new();
+        fast_log::shutdown().unwrap();
+        fast_log::init(Config::new().custom(third.clone())).unwrap();
+        log::info!("third");
+        log::logger().flush();
+        assert_eq!(third.messages(), vec!["third".to_string()]);
+    }
+
+    #[test]
+    fn test_reconfigure_same_file() {
+        let _ = remove_dir_all("target/test_reconfigure/");
+        log::set_max_level(LevelFilter::Trace);
+        let config = || {
+            Config::new().file_split(
+                "target/test_reconfigure/",
+                LogSize::MB(100),
+                RollingType::All,
+                LogPacker {},
+            )
+        };
+        let logger = Logger::new(config()).unwrap();
+        for i in 0..20000 {
+            log::info!(logger: logger, "old {}", i);
+        }
+        //the new appender opens the file while the old one is still writing
+        logger.reconfigure(config()).unwrap();
+        for i in 0..100 {
+            log::info!(logger: logger, "new {}", i);
+        }
+        logger.shutdown();
+        let data = read_to_string("target/test_reconfigure/temp.log").unwrap();
+        let lines: Vec<&str> = data.lines().collect();
+        assert_eq!(lines.len(), 20100);
+        assert!(lines[19999].ends_with(" old 19999"));
+        assert!(lines[20000].ends_with(" new 0"));
+        let _ = remove_dir_all("target/test_reconfigure/");
+    }
+}
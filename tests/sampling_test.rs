@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod test {
+    use fast_log::context;
+    use fast_log::filter::{Filter, Sample, SamplingFilter};
+    use log::{Level, Record};
+
+    fn kept(filter: &SamplingFilter, level: Level, n: usize) -> usize {
+        (0..n)
+            .filter(|_| {
+                !filter.filter(
+                    &Record::builder()
+                        .level(level)
+                        .target("sampling")
+                        .args(format_args!("yak"))
+                        .build(),
+                )
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_one_in() {
+        let filter = SamplingFilter::new().level(Level::Debug, Sample::OneIn(10));
+        assert_eq!(kept(&filter, Level::Debug, 100), 10);
+        assert_eq!(kept(&filter, Level::Info, 100), 100);
+    }
+
+    #[test]
+    fn test_one_in_interleaved_levels() {
+        let filter = SamplingFilter::new()
+            .level(Level::Debug, Sample::OneIn(2))
+            .level(Level::Trace, Sample::OneIn(2));
+        let (mut debug, mut trace) = (0, 0);
+        for _ in 0..100 {
+            debug += kept(&filter, Level::Debug, 1);
+            trace += kept(&filter, Level::Trace, 1);
+        }
+        assert_eq!(debug, 50);
+        assert_eq!(trace, 50);
+    }
+
+    #[test]
+    fn test_errors_never_sampled() {
+        let filter = SamplingFilter::new().level(Level::Error, Sample::Percent(0.0));
+        assert_eq!(kept(&filter, Level::Error, 100), 100);
+    }
+
+    #[test]
+    fn test_percent() {
+        let filter = SamplingFilter::new()
+            .level(Level::Debug, Sample::Percent(50.0))
+            .level(Level::Trace, Sample::Percent(0.0));
+        let debug = kept(&filter, Level::Debug, 10000);
+        assert!(debug > 4000 && debug < 6000);
+        assert_eq!(kept(&filter, Level::Trace, 100), 0);
+    }
+
+    #[test]
+    fn test_key() {
+        let filter =
+            SamplingFilter::new().level(Level::Debug, Sample::Key("request_id".to_string(), 50.0));
+        let keep = |id: u64| {
+            let kvs = [("request_id", id)];
+            !filter.filter(
+                &Record::builder()
+                    .level(Level::Debug)
+                    .args(format_args!("yak"))
+                    .key_values(&kvs)
+                    .build(),
+            )
+        };
+        //the same request is always kept or always dropped
+        for id in 0..100 {
+            let first = keep(id);
+            assert!((0..10).all(|_| keep(id) == first));
+        }
+        //and across processes and releases(FNV-1a of the value)
+        let kept_ids: Vec<u64> = (0..10).filter(|id| keep(*id)).collect();
+        assert_eq!(kept_ids, vec![2, 3, 4, 7, 8, 9]);
+        let sampled = (0..1000).filter(|id| keep(*id)).count();
+        assert!(sampled > 300 && sampled < 700);
+        //the key is looked up in the context too
+        let dropped = (0..100).find(|id| !keep(*id)).unwrap();
+        let _guard = context::push("request_id", dropped);
+        assert_eq!(kept(&filter, Level::Debug, 10), 0);
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod test {
+    use fast_log::appender::{FastLogRecord, LogAppender};
+    use fast_log::consts::LogSize;
+    use fast_log::plugin::file_split::KeepType;
+    use fast_log::plugin::packer::LogPacker;
+    use fast_log::Config;
+    use std::fs::remove_dir_all;
+
+    struct NopAppender {}
+
+    impl LogAppender for NopAppender {
+        fn do_logs(&self, _records: &[FastLogRecord]) {}
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let _ = remove_dir_all("target/test_shutdown/");
+        fast_log::init(Config::new().custom(NopAppender {}).file_split(
+            "target/test_shutdown/",
+            LogSize::KB(1),
+            KeepType::All,
+            LogPacker {},
+        ))
+        .unwrap();
+        for _ in 0..100 {
+            log::info!("Commencing yak shaving");
+        }
+        let summary = fast_log::shutdown().unwrap();
+        assert_eq!(summary.written, vec![100, 100]);
+        assert_eq!(summary.dropped, 0);
+        assert!(summary.errors.is_empty());
+        //not running anymore
+        assert!(fast_log::flush().is_err());
+        assert!(fast_log::shutdown().is_err());
+        let files = std::fs::read_dir("target/test_shutdown/").unwrap().count();
+        assert!(files > 1);
+        let _ = remove_dir_all("target/test_shutdown/");
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(all(test, unix, feature = "signal"))]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::{Config, LOGGER};
+    use log::Level;
+    use signal_hook::consts::SIGTERM;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_exit_on_signal() {
+        //a handler of the application, chained instead of terminating the test
+        let term = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register(SIGTERM, term.clone()).unwrap();
+        let capture = CaptureAppender::new();
+        fast_log::init(Config::new().exit_on_signal(true).custom(capture.clone())).unwrap();
+        log::info!("Commencing yak shaving");
+        signal_hook::low_level::raise(SIGTERM).unwrap();
+        assert!(capture.wait_for(2, Duration::from_secs(5)));
+        assert!(term.load(Ordering::SeqCst));
+        //the LOGGER has been shut down with every record written
+        assert!(LOGGER.config().is_none());
+        let records: Vec<(Level, String)> = capture
+            .records()
+            .iter()
+            .map(|x| (x.level, x.args.clone()))
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (Level::Info, "Commencing yak shaving".to_string()),
+                (Level::Warn, "received SIGTERM, shutting down".to_string()),
+            ]
+        );
+    }
+}
@@ -5,7 +5,8 @@ mod test {
     use fast_log::plugin::file_name::FileName;
     use fast_log::plugin::file_split::{FileSplitAppender, Keep, Packer, RawFile, RollingType};
     use fast_log::plugin::packer::LogPacker;
-    use log::Level;
+    use fast_log::{Config, Logger};
+    use log::{Level, LevelFilter, Log};
     use std::fs::remove_dir_all;
     use std::thread::sleep;
     use std::time::{Duration, SystemTime};
@@ -23,13 +24,18 @@ mod test {
         appender.do_logs(&[FastLogRecord {
             command: Command::CommandRecord,
             level: Level::Error,
-            target: "".to_string(),
+            target: "".into(),
             args: "".to_string(),
-            module_path: "".to_string(),
-            file: "".to_string(),
+            module_path: "".into(),
+            file: "".into(),
             line: None,
             now: SystemTime::now(),
+            thread_id: 0,
+            thread_name: None,
+            pid: 0,
             formated: "".to_string(),
+            fields: vec![],
+            context: None,
         }]);
         appender.send_pack();
         sleep(Duration::from_secs(1));
@@ -38,6 +44,43 @@ mod test {
         let _ = remove_dir_all("target/test/");
     }
 
+    #[test]
+    fn test_flush_waits_packs() {
+        let _ = remove_dir_all("target/test_flush/");
+        let logger = Logger::new(
+            Config::new().custom(
+                FileSplitAppender::<RawFile>::new(
+                    "target/test_flush/",
+                    LogSize::KB(1),
+                    RollingType::All,
+                    Box::new(LogPacker {}),
+                )
+                .unwrap()
+                .fsync(true),
+            ),
+        )
+        .unwrap();
+        log::set_max_level(LevelFilter::Trace);
+        for _ in 0..100 {
+            log::info!(logger: logger, "Commencing yak shaving");
+        }
+        logger.flush();
+        let metrics = logger.metrics().unwrap();
+        let rotations = metrics.appenders[0].rotations;
+        assert!(rotations > 0);
+        assert_eq!(metrics.appenders[0].packs, rotations);
+        //the saver keeps running after a flush
+        for _ in 0..100 {
+            log::info!(logger: logger, "Commencing yak shaving");
+        }
+        logger.flush();
+        let metrics = logger.metrics().unwrap();
+        assert!(metrics.appenders[0].rotations > rotations);
+        assert_eq!(metrics.appenders[0].packs, metrics.appenders[0].rotations);
+        let _ = logger.shutdown();
+        let _ = remove_dir_all("target/test_flush/");
+    }
+
     #[test]
     fn test_log_name_create() {
         let p = LogPacker {};
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod test {
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::suppress::{RateLimit, IDLE};
+    use fast_log::Config;
+    use log::Log;
+    use std::time::Duration;
+
+    #[test]
+    fn test_dedup() {
+        let (logger, capture) = CaptureAppender::logger(Config::new().dedup(true)).unwrap();
+        for _ in 0..100 {
+            log::warn!(logger: logger, "reconnect failed");
+        }
+        log::info!(logger: logger, "connected");
+        log::info!(logger: logger, "connected");
+        logger.flush();
+        assert_eq!(
+            capture.messages(),
+            vec![
+                "reconnect failed",
+                "[fast_log] last message repeated 99 times",
+                "connected",
+                "[fast_log] last message repeated 1 times",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedup_print_and_fields() {
+        let (logger, capture) = CaptureAppender::logger(Config::new().dedup(true)).unwrap();
+        logger.print("alpha\n".to_string()).unwrap();
+        logger.print("beta\n".to_string()).unwrap();
+        logger.print("beta\n".to_string()).unwrap();
+        log::info!(logger: logger, id = 1; "login");
+        log::info!(logger: logger, id = 2; "login");
+        log::info!(logger: logger, id = 2; "login");
+        logger.flush();
+        //print records carry their text in formated
+        let lines: Vec<String> = capture
+            .records()
+            .iter()
+            .map(|x| match x.args.is_empty() {
+                true => x.formated.trim_end().to_string(),
+                false => x.args.clone(),
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "alpha",
+                "beta",
+                "[fast_log] last message repeated 1 times",
+                "login",
+                "login",
+                "[fast_log] last message repeated 1 times",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let (logger, capture) = CaptureAppender::logger(
+            Config::new().rate_limit(RateLimit::per_call_site(10, Duration::from_secs(3600))),
+        )
+        .unwrap();
+        for i in 0..100 {
+            log::info!(logger: logger, "{}", i);
+        }
+        log::info!(logger: logger, "other call site");
+        logger.shutdown();
+        let lines = capture.messages();
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[9], "9");
+        assert_eq!(lines[10], "other call site");
+        assert_eq!(lines[11], "[fast_log] 90 records suppressed");
+    }
+
+    #[test]
+    fn test_rate_limit_reset() {
+        let (logger, capture) = CaptureAppender::logger(
+            Config::new().rate_limit(RateLimit::per_target(1, Duration::from_millis(100))),
+        )
+        .unwrap();
+        for _ in 0..10 {
+            log::info!(logger: logger, "flood");
+        }
+        std::thread::sleep(Duration::from_millis(200));
+        log::info!(logger: logger, "after");
+        logger.flush();
+        let lines = capture.messages();
+        assert_eq!(lines[0], "flood");
+        assert!(lines[lines.len() - 2].ends_with("records suppressed"));
+        assert_eq!(lines[lines.len() - 1], "after");
+    }
+
+    #[test]
+    fn test_dedup_idle() {
+        let (logger, capture) = CaptureAppender::logger(Config::new().dedup(true)).unwrap();
+        for _ in 0..5 {
+            log::warn!(logger: logger, "disk almost full");
+        }
+        //no later record, flush or exit: the summary is written after the idle window
+        assert!(capture.wait_for(2, IDLE * 5));
+        assert_eq!(
+            capture.messages(),
+            vec![
+                "disk almost full",
+                "[fast_log] last message repeated 4 times"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_idle() {
+        let (logger, capture) = CaptureAppender::logger(
+            Config::new().rate_limit(RateLimit::per_target(1, Duration::from_secs(3600))),
+        )
+        .unwrap();
+        for _ in 0..10 {
+            log::info!(logger: logger, "flood");
+        }
+        assert!(capture.wait_for(2, IDLE * 5));
+        assert_eq!(
+            capture.messages(),
+            vec!["flood", "[fast_log] 9 records suppressed"]
+        );
+    }
+}
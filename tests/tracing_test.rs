@@ -0,0 +1,50 @@
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use fast_log::appender::FieldValue;
+    use fast_log::plugin::capture::CaptureAppender;
+    use fast_log::tracing::FastLogLayer;
+    use fast_log::{Config, Logger};
+    use log::{Level, LevelFilter, Log};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_tracing_layer() {
+        log::set_max_level(LevelFilter::Trace);
+        let capture = CaptureAppender::new();
+        let logger: &'static Logger = Box::leak(Box::new(
+            Logger::new(
+                Config::new()
+                    .level(LevelFilter::Info)
+                    .custom(capture.clone()),
+            )
+            .unwrap(),
+        ));
+        let subscriber = tracing_subscriber::registry().with(FastLogLayer::with_logger(logger));
+        tracing::subscriber::with_default(subscriber, || {
+            let request = tracing::info_span!("request", request_id = 42);
+            let _request = request.enter();
+            let db = tracing::info_span!("db", table = "yaks");
+            let _db = db.enter();
+            tracing::warn!(latency = 12, ok = true, "slow query");
+            tracing::debug!("filtered by level");
+        });
+        logger.flush();
+        let records = capture.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, Level::Warn);
+        assert_eq!(records[0].args, "slow query");
+        assert_eq!(
+            records[0].fields,
+            vec![
+                (
+                    "span".to_string(),
+                    FieldValue::Str("request:db".to_string())
+                ),
+                ("request_id".to_string(), FieldValue::I64(42)),
+                ("table".to_string(), FieldValue::Str("yaks".to_string())),
+                ("latency".to_string(), FieldValue::I64(12)),
+                ("ok".to_string(), FieldValue::Bool(true)),
+            ]
+        );
+    }
+}